@@ -1,4 +1,13 @@
 use anchor_lang::prelude::*;
 
 #[constant]
-pub const SEED: &str = "anchor";
+pub const VESTING_SEED: &str = "vesting";
+
+#[constant]
+pub const DEAL_SEED: &str = "deal";
+
+#[constant]
+pub const MAX_MILESTONES: u8 = 8;
+
+#[constant]
+pub const NFT_OFFER_SEED: &str = "nft_offer";
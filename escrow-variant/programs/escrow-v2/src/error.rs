@@ -1,7 +1,29 @@
 use anchor_lang::prelude::*;
 
 #[error_code]
-pub enum ErrorCode {
-    #[msg("Custom error message")]
-    CustomError,
+pub enum EscrowError {
+    #[msg("amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("schedule must satisfy start <= cliff <= end with start < end")]
+    InvalidSchedule,
+    #[msg("nothing has vested since the last claim")]
+    NothingToClaim,
+    #[msg("overflow on calculation")]
+    Overflow,
+    #[msg("buyer, seller and arbiter must be distinct")]
+    InvalidParties,
+    #[msg("signer is not allowed to perform this action on the deal")]
+    Unauthorized,
+    #[msg("deal is not in a state that allows this action")]
+    InvalidDealState,
+    #[msg("milestones must be non-zero, at most MAX_MILESTONES and sum to the deal amount")]
+    InvalidMilestones,
+    #[msg("every milestone has already been paid")]
+    NoPendingMilestone,
+    #[msg("mint is not an NFT: supply must be 1 with 0 decimals")]
+    NotAnNft,
+    #[msg("NFT is not a verified member of the collection")]
+    CollectionNotVerified,
+    #[msg("mint does not match the offer")]
+    MintMismatch,
 }
@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    error::EscrowError,
+    state::{Deal, DealState},
+    vault::close_vault,
+    DEAL_SEED,
+};
+
+#[derive(Accounts)]
+pub struct ApproveMilestone<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub seller: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+
+    #[account(mut, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = seller,
+        associated_token::token_program = token_program
+    )]
+    pub seller_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = buyer,
+        has_one = seller,
+        has_one = creator,
+        has_one = mint,
+        seeds = [DEAL_SEED.as_bytes(), buyer.key().as_ref(), seller.key().as_ref(), deal.seed.to_le_bytes().as_ref()],
+        bump = deal.bump
+    )]
+    pub deal: Account<'info, Deal>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = deal,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ApproveMilestone<'info> {
+    // Pays the next milestone's tranche to the seller. Approving the last one
+    // completes the deal the same way `release` does.
+    pub fn approve_milestone(&mut self) -> Result<()> {
+        require!(
+            self.deal.state == DealState::Funded,
+            EscrowError::InvalidDealState
+        );
+        let amount = self.deal.next_tranche(self.vault.amount)?;
+        self.deal.milestones_paid += 1;
+
+        let buyer = self.buyer.key();
+        let seller = self.seller.key();
+        let seed = self.deal.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            DEAL_SEED.as_bytes(),
+            buyer.as_ref(),
+            seller.as_ref(),
+            seed.as_ref(),
+            &[self.deal.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.seller_ata.to_account_info(),
+            authority: self.deal.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        if (self.deal.milestones_paid as usize) < self.deal.milestones.len() {
+            return Ok(());
+        }
+
+        close_vault(
+            &self.token_program,
+            &self.mint,
+            &self.vault,
+            self.buyer.to_account_info(),
+            self.deal.to_account_info(),
+            &signer_seeds,
+        )?;
+
+        self.deal.close(self.creator.to_account_info())
+    }
+}
@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{state::Vesting, vault::close_vault, VESTING_SEED};
+
+#[derive(Accounts)]
+pub struct Cancel<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    pub beneficiary: SystemAccount<'info>,
+
+    #[account(mut, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program
+    )]
+    pub beneficiary_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = beneficiary,
+        has_one = mint,
+        seeds = [VESTING_SEED.as_bytes(), maker.key().as_ref(), vesting.seed.to_le_bytes().as_ref()],
+        bump = vesting.bump
+    )]
+    pub vesting: Account<'info, Vesting>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Cancel<'info> {
+    // Whatever has already vested still belongs to the beneficiary, so it is
+    // paid out before the unvested remainder goes back to the maker.
+    pub fn cancel(&mut self) -> Result<()> {
+        let vested = self
+            .vesting
+            .claimable_amount(Clock::get()?.unix_timestamp)?;
+        let unvested = self.vault.amount - vested;
+
+        let maker = self.maker.key();
+        let seed = self.vesting.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            VESTING_SEED.as_bytes(),
+            maker.as_ref(),
+            seed.as_ref(),
+            &[self.vesting.bump],
+        ]];
+
+        for (to, amount) in [(&self.beneficiary_ata, vested), (&self.maker_ata, unvested)] {
+            if amount == 0 {
+                continue;
+            }
+
+            let cpi_accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: self.vesting.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                cpi_accounts,
+                &signer_seeds,
+            );
+            transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        }
+
+        close_vault(
+            &self.token_program,
+            &self.mint,
+            &self.vault,
+            self.maker.to_account_info(),
+            self.vesting.to_account_info(),
+            &signer_seeds,
+        )
+    }
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::EscrowError,
+    state::{Deal, DealState},
+    DEAL_SEED,
+};
+
+#[derive(Accounts)]
+pub struct CancelDeal<'info> {
+    #[account(
+        constraint = authority.key() == deal.creator
+            || authority.key() == deal.buyer
+            || authority.key() == deal.seller @ EscrowError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator,
+        seeds = [DEAL_SEED.as_bytes(), deal.buyer.as_ref(), deal.seller.as_ref(), deal.seed.to_le_bytes().as_ref()],
+        bump = deal.bump
+    )]
+    pub deal: Account<'info, Deal>,
+}
+
+impl<'info> CancelDeal<'info> {
+    // Calls off a deal the buyer never funded and hands its rent back to the
+    // creator. A funded deal can only end through release or refund.
+    pub fn cancel_deal(&mut self) -> Result<()> {
+        require!(
+            self.deal.state == DealState::Created,
+            EscrowError::InvalidDealState
+        );
+        Ok(())
+    }
+}
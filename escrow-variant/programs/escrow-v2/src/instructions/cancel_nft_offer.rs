@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::EscrowError, state::NftOffer, vault::close_vault, NFT_OFFER_SEED};
+
+#[derive(Accounts)]
+pub struct CancelNftOffer<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    // The NFT for a listing, the payment mint for a collection bid.
+    #[account(
+        mut,
+        mint::token_program = token_program,
+        constraint = deposit_mint.key() == offer.deposit_mint() @ EscrowError::MintMismatch
+    )]
+    pub deposit_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = deposit_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [NFT_OFFER_SEED.as_bytes(), maker.key().as_ref(), offer.seed.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, NftOffer>,
+
+    #[account(
+        mut,
+        associated_token::mint = deposit_mint,
+        associated_token::authority = offer,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelNftOffer<'info> {
+    pub fn refund_and_close(&mut self) -> Result<()> {
+        let maker = self.maker.key();
+        let seed = self.offer.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            NFT_OFFER_SEED.as_bytes(),
+            maker.as_ref(),
+            seed.as_ref(),
+            &[self.offer.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.deposit_mint.to_account_info(),
+            to: self.maker_ata.to_account_info(),
+            authority: self.offer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        );
+        transfer_checked(cpi_ctx, self.vault.amount, self.deposit_mint.decimals)?;
+
+        close_vault(
+            &self.token_program,
+            &self.deposit_mint,
+            &self.vault,
+            self.maker.to_account_info(),
+            self.offer.to_account_info(),
+            &signer_seeds,
+        )
+    }
+}
@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::EscrowError, state::Vesting, vault::close_vault, VESTING_SEED};
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(mut, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program
+    )]
+    pub beneficiary_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = beneficiary,
+        has_one = mint,
+        seeds = [VESTING_SEED.as_bytes(), maker.key().as_ref(), vesting.seed.to_le_bytes().as_ref()],
+        bump = vesting.bump
+    )]
+    pub vesting: Account<'info, Vesting>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Claim<'info> {
+    pub fn claim(&mut self) -> Result<()> {
+        let amount = self
+            .vesting
+            .claimable_amount(Clock::get()?.unix_timestamp)?;
+        require!(amount > 0, EscrowError::NothingToClaim);
+        self.vesting.claimed_amount += amount;
+
+        let maker = self.maker.key();
+        let seed = self.vesting.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            VESTING_SEED.as_bytes(),
+            maker.as_ref(),
+            seed.as_ref(),
+            &[self.vesting.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.beneficiary_ata.to_account_info(),
+            authority: self.vesting.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+
+    // The last claim empties the vault, so the rent the maker put up for it
+    // and for the schedule goes back to them.
+    pub fn close_if_done(&mut self) -> Result<()> {
+        if self.vesting.claimed_amount < self.vesting.total_amount {
+            return Ok(());
+        }
+
+        let maker = self.maker.key();
+        let seed = self.vesting.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            VESTING_SEED.as_bytes(),
+            maker.as_ref(),
+            seed.as_ref(),
+            &[self.vesting.bump],
+        ]];
+
+        close_vault(
+            &self.token_program,
+            &self.mint,
+            &self.vault,
+            self.maker.to_account_info(),
+            self.vesting.to_account_info(),
+            &signer_seeds,
+        )?;
+
+        self.vesting.close(self.maker.to_account_info())
+    }
+}
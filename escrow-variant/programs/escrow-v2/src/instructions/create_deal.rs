@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenInterface};
+
+use crate::{
+    error::EscrowError,
+    state::{Deal, DealState, Milestone},
+    DEAL_SEED, MAX_MILESTONES,
+};
+
+#[derive(Accounts)]
+#[instruction(seed: u64, buyer: Pubkey, seller: Pubkey)]
+pub struct CreateDeal<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [DEAL_SEED.as_bytes(), buyer.as_ref(), seller.as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Deal::INIT_SPACE
+    )]
+    pub deal: Account<'info, Deal>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateDeal<'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_deal(
+        &mut self,
+        seed: u64,
+        buyer: Pubkey,
+        seller: Pubkey,
+        arbiter: Pubkey,
+        amount: u64,
+        milestones: Vec<Milestone>,
+        bumps: &CreateDealBumps,
+    ) -> Result<()> {
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require!(
+            buyer != seller && arbiter != buyer && arbiter != seller,
+            EscrowError::InvalidParties
+        );
+        // The PDA is keyed on the parties, so an outsider could otherwise
+        // squat their seeds with an arbiter of its choosing.
+        let creator = self.creator.key();
+        require!(
+            creator == buyer || creator == seller,
+            EscrowError::Unauthorized
+        );
+        if !milestones.is_empty() {
+            require!(
+                milestones.len() <= MAX_MILESTONES as usize
+                    && milestones.iter().all(|milestone| milestone.amount > 0),
+                EscrowError::InvalidMilestones
+            );
+            let total = milestones
+                .iter()
+                .try_fold(0u64, |total, milestone| total.checked_add(milestone.amount))
+                .ok_or(EscrowError::Overflow)?;
+            require!(total == amount, EscrowError::InvalidMilestones);
+        }
+
+        self.deal.set_inner(Deal {
+            seed,
+            buyer,
+            seller,
+            arbiter,
+            creator: self.creator.key(),
+            mint: self.mint.key(),
+            amount,
+            state: DealState::Created,
+            milestones,
+            milestones_paid: 0,
+            bump: bumps.deal,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::EscrowError,
+    state::{Deal, DealState},
+    DEAL_SEED,
+};
+
+#[derive(Accounts)]
+pub struct Dispute<'info> {
+    #[account(
+        constraint = party.key() == deal.buyer || party.key() == deal.seller @ EscrowError::Unauthorized
+    )]
+    pub party: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [DEAL_SEED.as_bytes(), deal.buyer.as_ref(), deal.seller.as_ref(), deal.seed.to_le_bytes().as_ref()],
+        bump = deal.bump
+    )]
+    pub deal: Account<'info, Deal>,
+}
+
+impl<'info> Dispute<'info> {
+    // Freezes the funded deal so only the arbiter can release or refund it.
+    pub fn dispute(&mut self) -> Result<()> {
+        require!(
+            self.deal.state == DealState::Funded,
+            EscrowError::InvalidDealState
+        );
+        self.deal.state = DealState::Disputed;
+        Ok(())
+    }
+}
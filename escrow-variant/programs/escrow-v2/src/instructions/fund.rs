@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    error::EscrowError,
+    state::{Deal, DealState},
+    DEAL_SEED,
+};
+
+#[derive(Accounts)]
+pub struct Fund<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    // The arbiter the buyer agreed to. The seller may have created the deal,
+    // so funding confirms they didn't pick someone else.
+    pub arbiter: SystemAccount<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program
+    )]
+    pub buyer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        has_one = buyer,
+        has_one = arbiter,
+        has_one = mint,
+        seeds = [DEAL_SEED.as_bytes(), deal.buyer.as_ref(), deal.seller.as_ref(), deal.seed.to_le_bytes().as_ref()],
+        bump = deal.bump
+    )]
+    pub deal: Account<'info, Deal>,
+
+    #[account(
+        init,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = deal,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Fund<'info> {
+    pub fn fund(&mut self) -> Result<()> {
+        require!(
+            self.deal.state == DealState::Created,
+            EscrowError::InvalidDealState
+        );
+
+        let cpi_accounts = TransferChecked {
+            from: self.buyer_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.buyer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, self.deal.amount, self.mint.decimals)?;
+
+        // Settlement pays out whatever the vault holds, net of any transfer fee.
+        self.vault.reload()?;
+        self.deal.amount = self.vault.amount;
+        self.deal.state = DealState::Funded;
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::EscrowError, state::Vesting, VESTING_SEED};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct Make<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    pub beneficiary: SystemAccount<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds = [VESTING_SEED.as_bytes(), maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Vesting::INIT_SPACE
+    )]
+    pub vesting: Account<'info, Vesting>,
+
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Make<'info> {
+    pub fn init_vesting(
+        &mut self,
+        seed: u64,
+        amount: u64,
+        start_ts: i64,
+        cliff_ts: i64,
+        end_ts: i64,
+        bumps: &MakeBumps,
+    ) -> Result<()> {
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require!(
+            start_ts < end_ts && start_ts <= cliff_ts && cliff_ts <= end_ts,
+            EscrowError::InvalidSchedule
+        );
+
+        self.vesting.set_inner(Vesting {
+            seed,
+            maker: self.maker.key(),
+            beneficiary: self.beneficiary.key(),
+            mint: self.mint.key(),
+            total_amount: amount,
+            claimed_amount: 0,
+            start_ts,
+            cliff_ts,
+            end_ts,
+            bump: bumps.vesting,
+        });
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, amount: u64) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.maker_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        // Transfer-fee mints withhold part of the deposit; only what landed
+        // in the vault is scheduled.
+        self.vault.reload()?;
+        self.vesting.total_amount = self.vault.amount;
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::EscrowError, state::NftOffer, NFT_OFFER_SEED};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeCollectionBid<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mint::token_program = token_program)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_payment_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds = [NFT_OFFER_SEED.as_bytes(), maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + NftOffer::INIT_SPACE
+    )]
+    pub offer: Account<'info, NftOffer>,
+
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = payment_mint,
+        associated_token::authority = offer,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeCollectionBid<'info> {
+    // Escrows `price` up front so any holder of a verified NFT from the
+    // collection can fill the bid without the maker signing again.
+    pub fn make_collection_bid(
+        &mut self,
+        seed: u64,
+        price: u64,
+        bumps: &MakeCollectionBidBumps,
+    ) -> Result<()> {
+        require!(price > 0, EscrowError::InvalidAmount);
+
+        let cpi_accounts = TransferChecked {
+            from: self.maker_payment_ata.to_account_info(),
+            mint: self.payment_mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, price, self.payment_mint.decimals)?;
+
+        // The taker gets what landed in the vault, net of any transfer fee.
+        self.vault.reload()?;
+        self.offer.set_inner(NftOffer {
+            seed,
+            maker: self.maker.key(),
+            collection: self.collection_mint.key(),
+            nft_mint: None,
+            payment_mint: self.payment_mint.key(),
+            price: self.vault.amount,
+            bump: bumps.offer,
+        });
+        Ok(())
+    }
+}
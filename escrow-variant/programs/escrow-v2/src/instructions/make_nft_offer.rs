@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{Metadata, MetadataAccount},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::EscrowError, state::NftOffer, NFT_OFFER_SEED};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeNftOffer<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        mint::token_program = nft_token_program,
+        constraint = nft_mint.supply == 1 && nft_mint.decimals == 0 @ EscrowError::NotAnNft
+    )]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mint::token_program = payment_token_program)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump,
+        constraint = metadata.collection.as_ref().is_some_and(|collection| collection.verified && collection.key == collection_mint.key()) @ EscrowError::CollectionNotVerified
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = nft_token_program
+    )]
+    pub maker_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds = [NFT_OFFER_SEED.as_bytes(), maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + NftOffer::INIT_SPACE
+    )]
+    pub offer: Account<'info, NftOffer>,
+
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = nft_mint,
+        associated_token::authority = offer,
+        associated_token::token_program = nft_token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub nft_token_program: Interface<'info, TokenInterface>,
+    pub payment_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeNftOffer<'info> {
+    pub fn make_nft_offer(
+        &mut self,
+        seed: u64,
+        price: u64,
+        bumps: &MakeNftOfferBumps,
+    ) -> Result<()> {
+        require!(price > 0, EscrowError::InvalidAmount);
+
+        self.offer.set_inner(NftOffer {
+            seed,
+            maker: self.maker.key(),
+            collection: self.collection_mint.key(),
+            nft_mint: Some(self.nft_mint.key()),
+            payment_mint: self.payment_mint.key(),
+            price,
+            bump: bumps.offer,
+        });
+
+        let cpi_accounts = TransferChecked {
+            from: self.maker_nft_ata.to_account_info(),
+            mint: self.nft_mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.nft_token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, 1, 0)
+    }
+}
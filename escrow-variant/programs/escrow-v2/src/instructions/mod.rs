@@ -1,3 +1,31 @@
-pub mod initialize;
+pub mod approve_milestone;
+pub mod cancel;
+pub mod cancel_deal;
+pub mod cancel_nft_offer;
+pub mod claim;
+pub mod create_deal;
+pub mod dispute;
+pub mod fund;
+pub mod make;
+pub mod make_collection_bid;
+pub mod make_nft_offer;
+pub mod refund;
+pub mod release;
+pub mod take_collection_bid;
+pub mod take_nft_offer;
 
-pub use initialize::*;
+pub use approve_milestone::*;
+pub use cancel::*;
+pub use cancel_deal::*;
+pub use cancel_nft_offer::*;
+pub use claim::*;
+pub use create_deal::*;
+pub use dispute::*;
+pub use fund::*;
+pub use make::*;
+pub use make_collection_bid::*;
+pub use make_nft_offer::*;
+pub use refund::*;
+pub use release::*;
+pub use take_collection_bid::*;
+pub use take_nft_offer::*;
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{state::Deal, vault::close_vault, DEAL_SEED};
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+
+    #[account(mut, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program
+    )]
+    pub buyer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = buyer,
+        has_one = creator,
+        has_one = mint,
+        seeds = [DEAL_SEED.as_bytes(), buyer.key().as_ref(), deal.seller.as_ref(), deal.seed.to_le_bytes().as_ref()],
+        bump = deal.bump
+    )]
+    pub deal: Account<'info, Deal>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = deal,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> Refund<'info> {
+    // Returns whatever the vault still holds, so on a milestone deal this
+    // cancels the milestones that haven't been paid yet. The deal is closed
+    // to whoever created it.
+    pub fn refund(&mut self) -> Result<()> {
+        self.deal
+            .check_settler(&self.authority.key(), &self.deal.seller)?;
+
+        let buyer = self.buyer.key();
+        let seed = self.deal.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            DEAL_SEED.as_bytes(),
+            buyer.as_ref(),
+            self.deal.seller.as_ref(),
+            seed.as_ref(),
+            &[self.deal.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.buyer_ata.to_account_info(),
+            authority: self.deal.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        );
+        transfer_checked(cpi_ctx, self.vault.amount, self.mint.decimals)?;
+
+        close_vault(
+            &self.token_program,
+            &self.mint,
+            &self.vault,
+            self.buyer.to_account_info(),
+            self.deal.to_account_info(),
+            &signer_seeds,
+        )
+    }
+}
@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{state::Deal, vault::close_vault, DEAL_SEED};
+
+#[derive(Accounts)]
+pub struct Release<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub buyer: SystemAccount<'info>,
+
+    pub seller: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub creator: SystemAccount<'info>,
+
+    #[account(mut, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = seller,
+        associated_token::token_program = token_program
+    )]
+    pub seller_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = buyer,
+        has_one = seller,
+        has_one = creator,
+        has_one = mint,
+        seeds = [DEAL_SEED.as_bytes(), buyer.key().as_ref(), seller.key().as_ref(), deal.seed.to_le_bytes().as_ref()],
+        bump = deal.bump
+    )]
+    pub deal: Account<'info, Deal>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = deal,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Release<'info> {
+    // Pays the seller and hands the vault rent back to the buyer who opened
+    // it. The deal itself is closed to whoever created it.
+    pub fn release(&mut self) -> Result<()> {
+        self.deal
+            .check_settler(&self.authority.key(), &self.deal.buyer)?;
+
+        let buyer = self.buyer.key();
+        let seller = self.seller.key();
+        let seed = self.deal.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            DEAL_SEED.as_bytes(),
+            buyer.as_ref(),
+            seller.as_ref(),
+            seed.as_ref(),
+            &[self.deal.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.seller_ata.to_account_info(),
+            authority: self.deal.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        );
+        transfer_checked(cpi_ctx, self.vault.amount, self.mint.decimals)?;
+
+        close_vault(
+            &self.token_program,
+            &self.mint,
+            &self.vault,
+            self.buyer.to_account_info(),
+            self.deal.to_account_info(),
+            &signer_seeds,
+        )
+    }
+}
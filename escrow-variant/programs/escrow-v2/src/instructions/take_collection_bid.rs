@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{Metadata, MetadataAccount},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::EscrowError, state::NftOffer, vault::close_vault, NFT_OFFER_SEED};
+
+#[derive(Accounts)]
+pub struct TakeCollectionBid<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        mint::token_program = nft_token_program,
+        constraint = nft_mint.supply == 1 && nft_mint.decimals == 0 @ EscrowError::NotAnNft
+    )]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, mint::token_program = payment_token_program)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"metadata", metadata_program.key().as_ref(), nft_mint.key().as_ref()],
+        seeds::program = metadata_program.key(),
+        bump,
+        constraint = metadata.collection.as_ref().is_some_and(|collection| collection.verified && collection.key == offer.collection) @ EscrowError::CollectionNotVerified
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = nft_token_program
+    )]
+    pub taker_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = nft_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = nft_token_program
+    )]
+    pub maker_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = payment_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = payment_token_program
+    )]
+    pub taker_payment_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = payment_mint,
+        constraint = offer.nft_mint.is_none() @ EscrowError::MintMismatch,
+        seeds = [NFT_OFFER_SEED.as_bytes(), maker.key().as_ref(), offer.seed.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, NftOffer>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = offer,
+        associated_token::token_program = payment_token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub metadata_program: Program<'info, Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub nft_token_program: Interface<'info, TokenInterface>,
+    pub payment_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeCollectionBid<'info> {
+    pub fn deposit_nft(&mut self) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.taker_nft_ata.to_account_info(),
+            mint: self.nft_mint.to_account_info(),
+            to: self.maker_nft_ata.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.nft_token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, 1, 0)
+    }
+
+    pub fn withdraw_and_close(&mut self) -> Result<()> {
+        let maker = self.maker.key();
+        let seed = self.offer.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            NFT_OFFER_SEED.as_bytes(),
+            maker.as_ref(),
+            seed.as_ref(),
+            &[self.offer.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.payment_mint.to_account_info(),
+            to: self.taker_payment_ata.to_account_info(),
+            authority: self.offer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.payment_token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        );
+        transfer_checked(cpi_ctx, self.vault.amount, self.payment_mint.decimals)?;
+
+        close_vault(
+            &self.payment_token_program,
+            &self.payment_mint,
+            &self.vault,
+            self.maker.to_account_info(),
+            self.offer.to_account_info(),
+            &signer_seeds,
+        )
+    }
+}
@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::EscrowError, state::NftOffer, NFT_OFFER_SEED};
+
+#[derive(Accounts)]
+pub struct TakeNftOffer<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(mint::token_program = nft_token_program)]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mint::token_program = payment_token_program)]
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = payment_token_program
+    )]
+    pub taker_payment_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = payment_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = payment_token_program
+    )]
+    pub maker_payment_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = nft_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = nft_token_program
+    )]
+    pub taker_nft_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        has_one = payment_mint,
+        constraint = offer.nft_mint == Some(nft_mint.key()) @ EscrowError::MintMismatch,
+        seeds = [NFT_OFFER_SEED.as_bytes(), maker.key().as_ref(), offer.seed.to_le_bytes().as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, NftOffer>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = offer,
+        associated_token::token_program = nft_token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub nft_token_program: Interface<'info, TokenInterface>,
+    pub payment_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeNftOffer<'info> {
+    pub fn pay(&mut self) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.taker_payment_ata.to_account_info(),
+            mint: self.payment_mint.to_account_info(),
+            to: self.maker_payment_ata.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.payment_token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, self.offer.price, self.payment_mint.decimals)
+    }
+
+    pub fn withdraw_nft_and_close(&mut self) -> Result<()> {
+        let maker = self.maker.key();
+        let seed = self.offer.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            NFT_OFFER_SEED.as_bytes(),
+            maker.as_ref(),
+            seed.as_ref(),
+            &[self.offer.bump],
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.nft_mint.to_account_info(),
+            to: self.taker_nft_ata.to_account_info(),
+            authority: self.offer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.nft_token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        );
+        transfer_checked(cpi_ctx, 1, 0)?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.offer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.nft_token_program.to_account_info(),
+            cpi_accounts,
+            &signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+}
@@ -2,6 +2,7 @@ pub mod constants;
 pub mod error;
 pub mod instructions;
 pub mod state;
+pub mod vault;
 
 use anchor_lang::prelude::*;
 
@@ -15,7 +16,88 @@ declare_id!("DDPu4T2aHsNCwZQx5Q8ihL9M8y9WTTQzwHcrU9ptpmgb");
 pub mod escrow_v2 {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        initialize::handler(ctx)
+    pub fn make(
+        ctx: Context<Make>,
+        seed: u64,
+        amount: u64,
+        start_ts: i64,
+        cliff_ts: i64,
+        end_ts: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_vesting(seed, amount, start_ts, cliff_ts, end_ts, &ctx.bumps)?;
+        ctx.accounts.deposit(amount)
+    }
+
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        ctx.accounts.claim()?;
+        ctx.accounts.close_if_done()
+    }
+
+    pub fn cancel(ctx: Context<Cancel>) -> Result<()> {
+        ctx.accounts.cancel()
+    }
+
+    pub fn create_deal(
+        ctx: Context<CreateDeal>,
+        seed: u64,
+        buyer: Pubkey,
+        seller: Pubkey,
+        arbiter: Pubkey,
+        amount: u64,
+        milestones: Vec<Milestone>,
+    ) -> Result<()> {
+        ctx.accounts
+            .create_deal(seed, buyer, seller, arbiter, amount, milestones, &ctx.bumps)
+    }
+
+    pub fn cancel_deal(ctx: Context<CancelDeal>) -> Result<()> {
+        ctx.accounts.cancel_deal()
+    }
+
+    pub fn fund(ctx: Context<Fund>) -> Result<()> {
+        ctx.accounts.fund()
+    }
+
+    pub fn release(ctx: Context<Release>) -> Result<()> {
+        ctx.accounts.release()
+    }
+
+    pub fn approve_milestone(ctx: Context<ApproveMilestone>) -> Result<()> {
+        ctx.accounts.approve_milestone()
+    }
+
+    pub fn refund(ctx: Context<Refund>) -> Result<()> {
+        ctx.accounts.refund()
+    }
+
+    pub fn dispute(ctx: Context<Dispute>) -> Result<()> {
+        ctx.accounts.dispute()
+    }
+
+    pub fn make_nft_offer(ctx: Context<MakeNftOffer>, seed: u64, price: u64) -> Result<()> {
+        ctx.accounts.make_nft_offer(seed, price, &ctx.bumps)
+    }
+
+    pub fn take_nft_offer(ctx: Context<TakeNftOffer>) -> Result<()> {
+        ctx.accounts.pay()?;
+        ctx.accounts.withdraw_nft_and_close()
+    }
+
+    pub fn make_collection_bid(
+        ctx: Context<MakeCollectionBid>,
+        seed: u64,
+        price: u64,
+    ) -> Result<()> {
+        ctx.accounts.make_collection_bid(seed, price, &ctx.bumps)
+    }
+
+    pub fn take_collection_bid(ctx: Context<TakeCollectionBid>) -> Result<()> {
+        ctx.accounts.deposit_nft()?;
+        ctx.accounts.withdraw_and_close()
+    }
+
+    pub fn cancel_nft_offer(ctx: Context<CancelNftOffer>) -> Result<()> {
+        ctx.accounts.refund_and_close()
     }
 }
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::EscrowError, MAX_MILESTONES};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum DealState {
+    Created,
+    Funded,
+    Disputed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Milestone {
+    pub amount: u64,
+    pub description_hash: [u8; 32],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Deal {
+    pub seed: u64,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub arbiter: Pubkey,
+    // Paid the deal's rent and gets it back once the deal settles.
+    pub creator: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub state: DealState,
+    // Paid out in order; empty for a deal that settles in one go.
+    #[max_len(MAX_MILESTONES)]
+    pub milestones: Vec<Milestone>,
+    pub milestones_paid: u8,
+    pub bump: u8,
+}
+
+impl Deal {
+    // Either side can settle in the other's favour (the buyer releases, the
+    // seller refunds) or the arbiter can decide. Once a deal is disputed only
+    // the arbiter can.
+    pub fn check_settler(&self, settler: &Pubkey, party: &Pubkey) -> Result<()> {
+        match self.state {
+            DealState::Funded => require!(
+                settler == party || *settler == self.arbiter,
+                EscrowError::Unauthorized
+            ),
+            DealState::Disputed => {
+                require_keys_eq!(*settler, self.arbiter, EscrowError::Unauthorized)
+            }
+            _ => return err!(EscrowError::InvalidDealState),
+        }
+        Ok(())
+    }
+
+    // The tranche the buyer approves next. The last one takes whatever the
+    // vault still holds, so a transfer fee withheld at funding comes out of it.
+    pub fn next_tranche(&self, vault_amount: u64) -> Result<u64> {
+        let Some(milestone) = self.milestones.get(self.milestones_paid as usize) else {
+            return err!(EscrowError::NoPendingMilestone);
+        };
+        if self.milestones_paid as usize + 1 == self.milestones.len() {
+            return Ok(vault_amount);
+        }
+        Ok(milestone.amount.min(vault_amount))
+    }
+}
@@ -0,0 +1,7 @@
+pub mod deal;
+pub mod nft_offer;
+pub mod vesting;
+
+pub use deal::*;
+pub use nft_offer::*;
+pub use vesting::*;
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct NftOffer {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub collection: Pubkey,
+    // The NFT the maker deposited, or `None` when the maker deposited
+    // `price` tokens for any NFT from `collection`.
+    pub nft_mint: Option<Pubkey>,
+    pub payment_mint: Pubkey,
+    pub price: u64,
+    pub bump: u8,
+}
+
+impl NftOffer {
+    // The mint held by the offer's vault.
+    pub fn deposit_mint(&self) -> Pubkey {
+        self.nft_mint.unwrap_or(self.payment_mint)
+    }
+}
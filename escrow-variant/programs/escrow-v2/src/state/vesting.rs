@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::error::EscrowError;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vesting {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+    pub bump: u8,
+}
+
+impl Vesting {
+    // Nothing vests before the cliff; after it the schedule is linear from
+    // `start_ts`, so the cliff releases everything accrued up to that point.
+    pub fn vested_amount(&self, now: i64) -> Result<u64> {
+        if now < self.cliff_ts {
+            return Ok(0);
+        }
+        if now >= self.end_ts {
+            return Ok(self.total_amount);
+        }
+
+        let elapsed = (now - self.start_ts) as u128;
+        let duration = (self.end_ts - self.start_ts) as u128;
+        u64::try_from(self.total_amount as u128 * elapsed / duration)
+            .map_err(|_| error!(EscrowError::Overflow))
+    }
+
+    pub fn claimable_amount(&self, now: i64) -> Result<u64> {
+        Ok(self.vested_amount(now)?.saturating_sub(self.claimed_amount))
+    }
+}
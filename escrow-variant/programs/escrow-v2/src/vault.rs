@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::TransferFeeAmount, BaseStateWithExtensions, StateWithExtensions,
+        },
+    },
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+};
+
+// Closes an emptied vault. Token-2022 refuses to close an account that still
+// has transfer fees withheld on it, so those are harvested to the mint first,
+// which is why every caller passes the mint writable.
+pub fn close_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    destination: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if withheld_fees(&vault.to_account_info())? > 0 {
+        let cpi_accounts = HarvestWithheldTokensToMint {
+            token_program_id: token_program.to_account_info(),
+            mint: mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+        harvest_withheld_tokens_to_mint(cpi_ctx, vec![vault.to_account_info()])?;
+    }
+
+    let cpi_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination,
+        authority,
+    };
+    let cpi_ctx =
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+    close_account(cpi_ctx)
+}
+
+fn withheld_fees(vault: &AccountInfo) -> Result<u64> {
+    if *vault.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = vault.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    Ok(state
+        .get_extension::<TransferFeeAmount>()
+        .map_or(0, |fee| u64::from(fee.withheld_amount)))
+}
@@ -1,16 +1,812 @@
+import { before, describe, it } from "node:test";
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { EscrowV2 } from "../target/types/escrow_v2";
+import * as spl from "@solana/spl-token";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { assert } from "chai";
+import { createUmi } from "@metaplex-foundation/umi-bundle-defaults";
+import { createSignerFromKeypair, generateSigner, percentAmount, publicKey, signerIdentity, some } from "@metaplex-foundation/umi";
+import { createNft, findMetadataPda, mplTokenMetadata, verifyCollectionV1 } from "@metaplex-foundation/mpl-token-metadata";
 
 describe("escrow-v2", () => {
-  // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
-
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
   const program = anchor.workspace.EscrowV2 as Program<EscrowV2>;
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+  const AMOUNT = new anchor.BN(1_000_000);
+  const DECIMALS = 6;
+
+  let maker: Keypair;
+  let beneficiary: Keypair;
+  let mint: PublicKey;
+  let makerAta: PublicKey;
+  let beneficiaryAta: PublicKey;
+  let feeMint: PublicKey;
+  let makerFeeAta: PublicKey;
+  let nextSeed = 1;
+
+  before(async () => {
+    maker = Keypair.generate();
+    beneficiary = Keypair.generate();
+    await fundAccount(maker.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
+    await fundAccount(beneficiary.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+
+    mint = await spl.createMint(provider.connection, maker, maker.publicKey, null, DECIMALS);
+    makerAta = await spl.createAssociatedTokenAccount(provider.connection, maker, mint, maker.publicKey);
+    beneficiaryAta = spl.getAssociatedTokenAddressSync(mint, beneficiary.publicKey);
+    await spl.mintTo(provider.connection, maker, mint, makerAta, maker, 10 * AMOUNT.toNumber());
+
+    feeMint = await createFeeMint();
+    makerFeeAta = await spl.createAssociatedTokenAccount(
+      provider.connection,
+      maker,
+      feeMint,
+      maker.publicKey,
+      undefined,
+      spl.TOKEN_2022_PROGRAM_ID
+    );
+    await spl.mintTo(
+      provider.connection,
+      maker,
+      feeMint,
+      makerFeeAta,
+      maker,
+      10 * AMOUNT.toNumber(),
+      [],
+      undefined,
+      spl.TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  function now(): number {
+    return Math.floor(Date.now() / 1000);
+  }
+
+  async function fundAccount(pubkey: PublicKey, amount: number) {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(pubkey, amount),
+      "confirmed"
+    );
+  }
+
+  async function balance(ata: PublicKey, tokenProgram = spl.TOKEN_PROGRAM_ID): Promise<number> {
+    const info = await provider.connection.getAccountInfo(ata);
+    return info ? Number((await spl.getAccount(provider.connection, ata, undefined, tokenProgram)).amount) : 0;
+  }
+
+  // A Token-2022 mint that withholds 1% of every transfer on the receiving
+  // account, so a vault that took deposits can't close until it's harvested.
+  async function createFeeMint(): Promise<PublicKey> {
+    const feeMint = Keypair.generate();
+    const space = spl.getMintLen([spl.ExtensionType.TransferFeeConfig]);
+    const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+    const tx = new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.createAccount({
+        fromPubkey: maker.publicKey,
+        newAccountPubkey: feeMint.publicKey,
+        space,
+        lamports,
+        programId: spl.TOKEN_2022_PROGRAM_ID,
+      }),
+      spl.createInitializeTransferFeeConfigInstruction(
+        feeMint.publicKey,
+        maker.publicKey,
+        maker.publicKey,
+        100,
+        BigInt(AMOUNT.toNumber()),
+        spl.TOKEN_2022_PROGRAM_ID
+      ),
+      spl.createInitializeMintInstruction(feeMint.publicKey, DECIMALS, maker.publicKey, null, spl.TOKEN_2022_PROGRAM_ID)
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [maker, feeMint]);
+    return feeMint.publicKey;
+  }
+
+  // Opens a schedule and returns its vesting PDA and vault. Pass `fee` to
+  // vest the transfer-fee mint instead of the plain one.
+  async function make(startTs: number, cliffTs: number, endTs: number, fee = false) {
+    const seed = new anchor.BN(nextSeed++);
+    const [vesting] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vesting"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const tokenProgram = fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID;
+    const vault = spl.getAssociatedTokenAddressSync(fee ? feeMint : mint, vesting, true, tokenProgram);
+
+    await program.methods
+      .make(seed, AMOUNT, new anchor.BN(startTs), new anchor.BN(cliffTs), new anchor.BN(endTs))
+      .accountsPartial({
+        maker: maker.publicKey,
+        beneficiary: beneficiary.publicKey,
+        mint: fee ? feeMint : mint,
+        makerAta: fee ? makerFeeAta : makerAta,
+        vesting,
+        vault,
+        tokenProgram,
+      })
+      .signers([maker])
+      .rpc();
+    return { vesting, vault };
+  }
+
+  function claim(vesting: PublicKey, vault: PublicKey, fee = false) {
+    return program.methods
+      .claim()
+      .accountsPartial({
+        beneficiary: beneficiary.publicKey,
+        maker: maker.publicKey,
+        mint: fee ? feeMint : mint,
+        vesting,
+        vault,
+        tokenProgram: fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([beneficiary])
+      .rpc();
+  }
+
+  function cancel(vesting: PublicKey, vault: PublicKey, fee = false) {
+    return program.methods
+      .cancel()
+      .accountsPartial({
+        maker: maker.publicKey,
+        beneficiary: beneficiary.publicKey,
+        mint: fee ? feeMint : mint,
+        makerAta: fee ? makerFeeAta : makerAta,
+        vesting,
+        vault,
+        tokenProgram: fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+  }
+
+  it("rejects a schedule that ends before it starts", async () => {
+    try {
+      await make(now(), now(), now() - 10);
+      assert.fail("make should have failed");
+    } catch (err) {
+      assert.include(err.message, "InvalidSchedule");
+    }
+  });
+
+  it("releases nothing before the cliff", async () => {
+    const { vesting, vault } = await make(now() - 100, now() + 3600, now() + 7200);
+
+    try {
+      await claim(vesting, vault);
+      assert.fail("claim should have failed");
+    } catch (err) {
+      assert.include(err.message, "NothingToClaim");
+    }
+  });
+
+  it("releases the whole deposit once fully vested and closes the schedule", async () => {
+    const { vesting, vault } = await make(now() - 200, now() - 100, now() - 1);
+    const before = await balance(beneficiaryAta);
+
+    await claim(vesting, vault);
+
+    assert.equal((await balance(beneficiaryAta)) - before, AMOUNT.toNumber());
+    assert.isNull(await provider.connection.getAccountInfo(vesting));
+    assert.isNull(await provider.connection.getAccountInfo(vault));
+  });
+
+  it("releases the linearly vested share after the cliff", async () => {
+    const { vesting, vault } = await make(now() - 1000, now() - 1000, now() + 1000);
+    const before = await balance(beneficiaryAta);
+
+    await claim(vesting, vault);
+
+    // About half has vested; allow for clock drift against the validator.
+    const claimed = (await balance(beneficiaryAta)) - before;
+    assert.approximately(claimed, AMOUNT.toNumber() / 2, AMOUNT.toNumber() / 20);
+
+    const account = await program.account.vesting.fetch(vesting);
+    assert.equal(account.claimedAmount.toNumber(), claimed);
+  });
+
+  it("pays out the vested share and returns the rest to the maker on cancel", async () => {
+    const { vesting, vault } = await make(now() - 1000, now() - 1000, now() + 1000);
+    const beneficiaryBefore = await balance(beneficiaryAta);
+    const makerBefore = await balance(makerAta);
+
+    await cancel(vesting, vault);
+
+    const vested = (await balance(beneficiaryAta)) - beneficiaryBefore;
+    const returned = (await balance(makerAta)) - makerBefore;
+    assert.isTrue(vested > 0 && returned > 0);
+    assert.equal(vested + returned, AMOUNT.toNumber());
+    assert.isNull(await provider.connection.getAccountInfo(vesting));
+    assert.isNull(await provider.connection.getAccountInfo(vault));
+  });
+
+  it("harvests withheld transfer fees so the last claim still closes the vault", async () => {
+    const { vesting, vault } = await make(now() - 200, now() - 100, now() - 1, true);
+    const beneficiaryFeeAta = spl.getAssociatedTokenAddressSync(
+      feeMint,
+      beneficiary.publicKey,
+      false,
+      spl.TOKEN_2022_PROGRAM_ID
+    );
+    const before = await balance(beneficiaryFeeAta, spl.TOKEN_2022_PROGRAM_ID);
+
+    await claim(vesting, vault, true);
+
+    // 1% is withheld going into the vault and again coming out of it.
+    assert.equal((await balance(beneficiaryFeeAta, spl.TOKEN_2022_PROGRAM_ID)) - before, 980_100);
+    assert.isNull(await provider.connection.getAccountInfo(vesting));
+    assert.isNull(await provider.connection.getAccountInfo(vault));
+  });
+
+  it("harvests withheld transfer fees so a cancel still closes the vault", async () => {
+    const { vesting, vault } = await make(now() - 1000, now() - 1000, now() + 1000, true);
+
+    await cancel(vesting, vault, true);
+
+    assert.isNull(await provider.connection.getAccountInfo(vesting));
+    assert.isNull(await provider.connection.getAccountInfo(vault));
+  });
+
+  describe("arbiter deals", () => {
+    let buyer: Keypair;
+    let seller: Keypair;
+    let arbiter: Keypair;
+    let buyerAta: PublicKey;
+    let sellerAta: PublicKey;
+    let buyerFeeAta: PublicKey;
+
+    before(async () => {
+      [buyer, seller, arbiter] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      for (const kp of [buyer, seller, arbiter]) {
+        await fundAccount(kp.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      }
+      buyerAta = await spl.createAssociatedTokenAccount(provider.connection, buyer, mint, buyer.publicKey);
+      sellerAta = spl.getAssociatedTokenAddressSync(mint, seller.publicKey);
+      await spl.mintTo(provider.connection, maker, mint, buyerAta, maker, 10 * AMOUNT.toNumber());
+
+      buyerFeeAta = await spl.createAssociatedTokenAccount(
+        provider.connection,
+        buyer,
+        feeMint,
+        buyer.publicKey,
+        undefined,
+        spl.TOKEN_2022_PROGRAM_ID
+      );
+      await spl.mintTo(
+        provider.connection,
+        maker,
+        feeMint,
+        buyerFeeAta,
+        maker,
+        10 * AMOUNT.toNumber(),
+        [],
+        undefined,
+        spl.TOKEN_2022_PROGRAM_ID
+      );
+    });
+
+    // Creates and funds a deal, returning its PDA and vault. Pass `fee` to
+    // settle it in the transfer-fee mint instead of the plain one.
+    async function fundedDeal(milestones: { amount: anchor.BN; descriptionHash: number[] }[] = [], fee = false) {
+      const seed = new anchor.BN(nextSeed++);
+      const [deal] = PublicKey.findProgramAddressSync(
+        [Buffer.from("deal"), buyer.publicKey.toBuffer(), seller.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const tokenProgram = fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID;
+      const dealMint = fee ? feeMint : mint;
+      const vault = spl.getAssociatedTokenAddressSync(dealMint, deal, true, tokenProgram);
+
+      await program.methods
+        .createDeal(seed, buyer.publicKey, seller.publicKey, arbiter.publicKey, AMOUNT, milestones)
+        .accountsPartial({ creator: buyer.publicKey, mint: dealMint, deal, tokenProgram })
+        .signers([buyer])
+        .rpc();
+      await program.methods
+        .fund()
+        .accountsPartial({
+          buyer: buyer.publicKey,
+          arbiter: arbiter.publicKey,
+          mint: dealMint,
+          buyerAta: fee ? buyerFeeAta : buyerAta,
+          deal,
+          vault,
+          tokenProgram,
+        })
+        .signers([buyer])
+        .rpc();
+      return { deal, vault };
+    }
+
+    function release(authority: Keypair, deal: PublicKey, vault: PublicKey, fee = false) {
+      return program.methods
+        .release()
+        .accountsPartial({
+          authority: authority.publicKey,
+          buyer: buyer.publicKey,
+          seller: seller.publicKey,
+          creator: buyer.publicKey,
+          mint: fee ? feeMint : mint,
+          deal,
+          vault,
+          tokenProgram: fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+    }
+
+    function refund(authority: Keypair, deal: PublicKey, vault: PublicKey, fee = false) {
+      return program.methods
+        .refund()
+        .accountsPartial({
+          authority: authority.publicKey,
+          buyer: buyer.publicKey,
+          creator: buyer.publicKey,
+          mint: fee ? feeMint : mint,
+          buyerAta: fee ? buyerFeeAta : buyerAta,
+          deal,
+          vault,
+          tokenProgram: fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+    }
+
+    it("rejects a deal where the arbiter is one of the parties", async () => {
+      try {
+        await program.methods
+          .createDeal(new anchor.BN(nextSeed++), buyer.publicKey, seller.publicKey, buyer.publicKey, AMOUNT, [])
+          .accountsPartial({ creator: buyer.publicKey, mint, tokenProgram: spl.TOKEN_PROGRAM_ID })
+          .signers([buyer])
+          .rpc();
+        assert.fail("createDeal should have failed");
+      } catch (err) {
+        assert.include(err.message, "InvalidParties");
+      }
+    });
+
+    it("rejects a deal created by someone other than the buyer or seller", async () => {
+      try {
+        await program.methods
+          .createDeal(new anchor.BN(nextSeed++), buyer.publicKey, seller.publicKey, arbiter.publicKey, AMOUNT, [])
+          .accountsPartial({ creator: arbiter.publicKey, mint, tokenProgram: spl.TOKEN_PROGRAM_ID })
+          .signers([arbiter])
+          .rpc();
+        assert.fail("createDeal should have failed");
+      } catch (err) {
+        assert.include(err.message, "Unauthorized");
+      }
+    });
+
+    it("refuses to fund a deal with an arbiter the buyer didn't expect", async () => {
+      const seed = new anchor.BN(nextSeed++);
+      const [deal] = PublicKey.findProgramAddressSync(
+        [Buffer.from("deal"), buyer.publicKey.toBuffer(), seller.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const sellersPick = Keypair.generate().publicKey;
+      await program.methods
+        .createDeal(seed, buyer.publicKey, seller.publicKey, sellersPick, AMOUNT, [])
+        .accountsPartial({ creator: seller.publicKey, mint, deal, tokenProgram: spl.TOKEN_PROGRAM_ID })
+        .signers([seller])
+        .rpc();
+
+      try {
+        await program.methods
+          .fund()
+          .accountsPartial({
+            buyer: buyer.publicKey,
+            arbiter: arbiter.publicKey,
+            mint,
+            buyerAta,
+            deal,
+            vault: spl.getAssociatedTokenAddressSync(mint, deal, true),
+            tokenProgram: spl.TOKEN_PROGRAM_ID,
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("fund should have failed");
+      } catch (err) {
+        assert.include(err.message, "ConstraintHasOne");
+      }
+    });
+
+    it("lets either party call off a deal that was never funded", async () => {
+      const seed = new anchor.BN(nextSeed++);
+      const [deal] = PublicKey.findProgramAddressSync(
+        [Buffer.from("deal"), buyer.publicKey.toBuffer(), seller.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createDeal(seed, buyer.publicKey, seller.publicKey, arbiter.publicKey, AMOUNT, [])
+        .accountsPartial({ creator: buyer.publicKey, mint, deal, tokenProgram: spl.TOKEN_PROGRAM_ID })
+        .signers([buyer])
+        .rpc();
+
+      await program.methods
+        .cancelDeal()
+        .accountsPartial({ authority: seller.publicKey, creator: buyer.publicKey, deal })
+        .signers([seller])
+        .rpc();
+
+      assert.isNull(await provider.connection.getAccountInfo(deal));
+    });
+
+    it("does not call off a funded deal", async () => {
+      const { deal } = await fundedDeal();
+
+      try {
+        await program.methods
+          .cancelDeal()
+          .accountsPartial({ authority: buyer.publicKey, creator: buyer.publicKey, deal })
+          .signers([buyer])
+          .rpc();
+        assert.fail("cancelDeal should have failed");
+      } catch (err) {
+        assert.include(err.message, "InvalidDealState");
+      }
+    });
+
+    it("pays the seller when the buyer releases", async () => {
+      const { deal, vault } = await fundedDeal();
+      const before = await balance(sellerAta);
+
+      await release(buyer, deal, vault);
+
+      assert.equal((await balance(sellerAta)) - before, AMOUNT.toNumber());
+      assert.isNull(await provider.connection.getAccountInfo(deal));
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+    });
+
+    it("returns the funds when the seller refunds", async () => {
+      const { deal, vault } = await fundedDeal();
+      const before = await balance(buyerAta);
+
+      await refund(seller, deal, vault);
+
+      assert.equal((await balance(buyerAta)) - before, AMOUNT.toNumber());
+      assert.isNull(await provider.connection.getAccountInfo(deal));
+    });
+
+    it("closes a transfer-fee vault on release despite withheld fees", async () => {
+      const { deal, vault } = await fundedDeal([], true);
+
+      await release(buyer, deal, vault, true);
+
+      assert.isNull(await provider.connection.getAccountInfo(deal));
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+    });
+
+    it("closes a transfer-fee vault on refund despite withheld fees", async () => {
+      const { deal, vault } = await fundedDeal([], true);
+
+      await refund(seller, deal, vault, true);
+
+      assert.isNull(await provider.connection.getAccountInfo(deal));
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+    });
+
+    it("does not let a party release its own side", async () => {
+      const { deal, vault } = await fundedDeal();
+
+      try {
+        await release(seller, deal, vault);
+        assert.fail("release should have failed");
+      } catch (err) {
+        assert.include(err.message, "Unauthorized");
+      }
+    });
+
+    it("leaves a disputed deal to the arbiter", async () => {
+      const { deal, vault } = await fundedDeal();
+
+      await program.methods
+        .dispute()
+        .accountsPartial({ party: seller.publicKey, deal })
+        .signers([seller])
+        .rpc();
+      assert.deepEqual((await program.account.deal.fetch(deal)).state, { disputed: {} });
+
+      try {
+        await release(buyer, deal, vault);
+        assert.fail("release should have failed");
+      } catch (err) {
+        assert.include(err.message, "Unauthorized");
+      }
+
+      const before = await balance(buyerAta);
+      await refund(arbiter, deal, vault);
+      assert.equal((await balance(buyerAta)) - before, AMOUNT.toNumber());
+    });
+
+    describe("milestones", () => {
+      const milestone = (amount: number, tag: number) => ({
+        amount: new anchor.BN(amount),
+        descriptionHash: Array(32).fill(tag),
+      });
+      const MILESTONES = [milestone(300_000, 1), milestone(300_000, 2), milestone(400_000, 3)];
+
+      function approve(deal: PublicKey, vault: PublicKey, fee = false) {
+        return program.methods
+          .approveMilestone()
+          .accountsPartial({
+            buyer: buyer.publicKey,
+            seller: seller.publicKey,
+            creator: buyer.publicKey,
+            mint: fee ? feeMint : mint,
+            deal,
+            vault,
+            tokenProgram: fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID,
+          })
+          .signers([buyer])
+          .rpc();
+      }
+
+      it("rejects milestones that don't add up to the deal amount", async () => {
+        try {
+          await fundedDeal([milestone(300_000, 1)]);
+          assert.fail("createDeal should have failed");
+        } catch (err) {
+          assert.include(err.message, "InvalidMilestones");
+        }
+      });
+
+      it("pays one tranche per approval and completes on the last", async () => {
+        const { deal, vault } = await fundedDeal(MILESTONES);
+        const before = await balance(sellerAta);
+
+        await approve(deal, vault);
+        assert.equal((await balance(sellerAta)) - before, 300_000);
+        assert.equal((await program.account.deal.fetch(deal)).milestonesPaid, 1);
+
+        await approve(deal, vault);
+        await approve(deal, vault);
+        assert.equal((await balance(sellerAta)) - before, AMOUNT.toNumber());
+        assert.isNull(await provider.connection.getAccountInfo(deal));
+        assert.isNull(await provider.connection.getAccountInfo(vault));
+      });
+
+      it("closes a transfer-fee vault on the last approval despite withheld fees", async () => {
+        const { deal, vault } = await fundedDeal(MILESTONES, true);
+
+        for (let i = 0; i < MILESTONES.length; i++) {
+          await approve(deal, vault, true);
+        }
+
+        assert.isNull(await provider.connection.getAccountInfo(deal));
+        assert.isNull(await provider.connection.getAccountInfo(vault));
+      });
+
+      it("refunds the unpaid milestones to the buyer", async () => {
+        const { deal, vault } = await fundedDeal(MILESTONES);
+        await approve(deal, vault);
+        const before = await balance(buyerAta);
+
+        await refund(seller, deal, vault);
+
+        assert.equal((await balance(buyerAta)) - before, 700_000);
+        assert.isNull(await provider.connection.getAccountInfo(deal));
+      });
+    });
+  });
+
+  describe("nft offers", () => {
+    const PRICE = new anchor.BN(500_000);
+    const umi = createUmi(provider.connection.rpcEndpoint).use(mplTokenMetadata());
+
+    let taker: Keypair;
+    let takerAta: PublicKey;
+    let collection: PublicKey;
+
+    before(async () => {
+      taker = Keypair.generate();
+      await fundAccount(taker.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      takerAta = await spl.createAssociatedTokenAccount(provider.connection, taker, mint, taker.publicKey);
+      await spl.mintTo(provider.connection, maker, mint, takerAta, maker, 10 * PRICE.toNumber());
+
+      // The maker is the collection's update authority and verifies its items.
+      umi.use(signerIdentity(createSignerFromKeypair(umi, umi.eddsa.createKeypairFromSecretKey(maker.secretKey))));
+      const collectionMint = generateSigner(umi);
+      await createNft(umi, {
+        mint: collectionMint,
+        name: "Escrow Collection",
+        uri: "https://example.com/collection.json",
+        sellerFeeBasisPoints: percentAmount(0),
+        isCollection: true,
+      }).sendAndConfirm(umi);
+      collection = new PublicKey(collectionMint.publicKey);
+    });
+
+    // Mints an NFT in the collection to `owner`, verified unless told otherwise.
+    async function mintNft(owner: PublicKey, verified = true): Promise<PublicKey> {
+      const nft = generateSigner(umi);
+      await createNft(umi, {
+        mint: nft,
+        name: "Escrow Item",
+        uri: "https://example.com/item.json",
+        sellerFeeBasisPoints: percentAmount(0),
+        tokenOwner: publicKey(owner.toBase58()),
+        collection: some({ key: publicKey(collection.toBase58()), verified: false }),
+      }).sendAndConfirm(umi);
+      if (verified) {
+        await verifyCollectionV1(umi, {
+          metadata: findMetadataPda(umi, { mint: nft.publicKey }),
+          collectionMint: publicKey(collection.toBase58()),
+        }).sendAndConfirm(umi);
+      }
+      return new PublicKey(nft.publicKey);
+    }
+
+    function deriveOffer(seed: anchor.BN): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("nft_offer"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    }
+
+    function makeNftOffer(seed: anchor.BN, nftMint: PublicKey) {
+      const offer = deriveOffer(seed);
+      return program.methods
+        .makeNftOffer(seed, PRICE)
+        .accountsPartial({
+          maker: maker.publicKey,
+          nftMint,
+          collectionMint: collection,
+          paymentMint: mint,
+          offer,
+          vault: spl.getAssociatedTokenAddressSync(nftMint, offer, true),
+          nftTokenProgram: spl.TOKEN_PROGRAM_ID,
+          paymentTokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+    }
+
+    // Escrows PRICE of the plain mint, or of the transfer-fee mint with `fee`,
+    // for any NFT in the collection. Returns the offer and its vault.
+    async function makeCollectionBid(seed: anchor.BN, fee = false) {
+      const offer = deriveOffer(seed);
+      const tokenProgram = fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID;
+      const vault = spl.getAssociatedTokenAddressSync(fee ? feeMint : mint, offer, true, tokenProgram);
+      await program.methods
+        .makeCollectionBid(seed, PRICE)
+        .accountsPartial({
+          maker: maker.publicKey,
+          collectionMint: collection,
+          paymentMint: fee ? feeMint : mint,
+          makerPaymentAta: fee ? makerFeeAta : makerAta,
+          offer,
+          vault,
+          tokenProgram,
+        })
+        .signers([maker])
+        .rpc();
+      return { offer, vault };
+    }
+
+    // Fills a collection bid with `nftMint`, which is always a classic SPL
+    // NFT, whatever token program the bid pays in.
+    function takeCollectionBid(offer: PublicKey, vault: PublicKey, nftMint: PublicKey, fee = false) {
+      return program.methods
+        .takeCollectionBid()
+        .accountsPartial({
+          taker: taker.publicKey,
+          maker: maker.publicKey,
+          nftMint,
+          paymentMint: fee ? feeMint : mint,
+          offer,
+          vault,
+          nftTokenProgram: spl.TOKEN_PROGRAM_ID,
+          paymentTokenProgram: fee ? spl.TOKEN_2022_PROGRAM_ID : spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([taker])
+        .rpc();
+    }
+
+    it("swaps a listed NFT for the asking price", async () => {
+      const seed = new anchor.BN(nextSeed++);
+      const nftMint = await mintNft(maker.publicKey);
+      await makeNftOffer(seed, nftMint);
+      const makerBefore = await balance(makerAta);
+
+      const offer = deriveOffer(seed);
+      await program.methods
+        .takeNftOffer()
+        .accountsPartial({
+          taker: taker.publicKey,
+          maker: maker.publicKey,
+          nftMint,
+          paymentMint: mint,
+          takerPaymentAta: takerAta,
+          offer,
+          vault: spl.getAssociatedTokenAddressSync(nftMint, offer, true),
+          nftTokenProgram: spl.TOKEN_PROGRAM_ID,
+          paymentTokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([taker])
+        .rpc();
+
+      assert.equal(await balance(spl.getAssociatedTokenAddressSync(nftMint, taker.publicKey)), 1);
+      assert.equal((await balance(makerAta)) - makerBefore, PRICE.toNumber());
+      assert.isNull(await provider.connection.getAccountInfo(offer));
+    });
+
+    it("refuses to list an NFT whose collection isn't verified", async () => {
+      const nftMint = await mintNft(maker.publicKey, false);
+
+      try {
+        await makeNftOffer(new anchor.BN(nextSeed++), nftMint);
+        assert.fail("makeNftOffer should have failed");
+      } catch (err) {
+        assert.include(err.message, "CollectionNotVerified");
+      }
+    });
+
+    it("fills a collection bid with any verified NFT from the collection", async () => {
+      const { offer, vault } = await makeCollectionBid(new anchor.BN(nextSeed++));
+
+      const nftMint = await mintNft(taker.publicKey);
+      const takerBefore = await balance(takerAta);
+      await takeCollectionBid(offer, vault, nftMint);
+
+      assert.equal(await balance(spl.getAssociatedTokenAddressSync(nftMint, maker.publicKey)), 1);
+      assert.equal((await balance(takerAta)) - takerBefore, PRICE.toNumber());
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+    });
+
+    it("hands the NFT back when the maker cancels a listing", async () => {
+      const seed = new anchor.BN(nextSeed++);
+      const nftMint = await mintNft(maker.publicKey);
+      await makeNftOffer(seed, nftMint);
+
+      const offer = deriveOffer(seed);
+      await program.methods
+        .cancelNftOffer()
+        .accountsPartial({
+          maker: maker.publicKey,
+          depositMint: nftMint,
+          offer,
+          vault: spl.getAssociatedTokenAddressSync(nftMint, offer, true),
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+
+      assert.equal(await balance(spl.getAssociatedTokenAddressSync(nftMint, maker.publicKey)), 1);
+      assert.isNull(await provider.connection.getAccountInfo(offer));
+    });
+
+    it("pays a Token-2022 collection bid for an SPL Token NFT and closes the vault", async () => {
+      const { offer, vault } = await makeCollectionBid(new anchor.BN(nextSeed++), true);
+      const nftMint = await mintNft(taker.publicKey);
+
+      await takeCollectionBid(offer, vault, nftMint, true);
+
+      // 1% is withheld going into the vault and again coming out of it.
+      const takerFeeAta = spl.getAssociatedTokenAddressSync(feeMint, taker.publicKey, false, spl.TOKEN_2022_PROGRAM_ID);
+      assert.equal(await balance(takerFeeAta, spl.TOKEN_2022_PROGRAM_ID), 490_050);
+      assert.equal(await balance(spl.getAssociatedTokenAddressSync(nftMint, maker.publicKey)), 1);
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+    });
+
+    it("closes a transfer-fee vault when the maker cancels a collection bid", async () => {
+      const { offer, vault } = await makeCollectionBid(new anchor.BN(nextSeed++), true);
+
+      await program.methods
+        .cancelNftOffer()
+        .accountsPartial({
+          maker: maker.publicKey,
+          depositMint: feeMint,
+          offer,
+          vault,
+          tokenProgram: spl.TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+
+      assert.isNull(await provider.connection.getAccountInfo(offer));
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+    });
   });
 });
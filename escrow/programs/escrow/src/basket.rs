@@ -0,0 +1,108 @@
+//! Helpers for the extra legs of a basket offer.
+//!
+//! Basket legs are passed as remaining accounts, `LEG_ACCOUNTS` per leg and in
+//! the order stored on the escrow: every `extra_deposits` leg first, then every
+//! `extra_requests` leg. Each chunk is `[mint, source, destination]`:
+//!
+//! - make: deposit legs `[mint, maker_ata, vault]`
+//! - take: deposit legs `[mint, vault, taker_ata]`, then request legs
+//!   `[mint, taker_ata, maker_ata]`
+//! - refund/expire: deposit legs `[mint, vault, maker_ata]`
+//!
+//! Whatever follows the leg accounts is forwarded to every token transfer of
+//! the instruction as transfer-hook extra accounts. Leg mints with a transfer
+//! fee have to be passed writable wherever a leg vault is closed, so fees
+//! withheld on the vault can be harvested first.
+
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{Mint, TokenAccount},
+};
+
+use crate::{constants::{LEG_ACCOUNTS, MAX_BASKET_LEGS}, error::EscrowError, extensions, state::Leg};
+
+pub fn validate_legs(primary: Pubkey, legs: &[Leg]) -> Result<()> {
+    require!(legs.len() <= MAX_BASKET_LEGS as usize, EscrowError::TooManyLegs);
+
+    for (i, leg) in legs.iter().enumerate() {
+        require!(leg.amount > 0, EscrowError::InvalidAmount);
+        require!(leg.mint != primary, EscrowError::DuplicateMint);
+        require!(
+            legs[..i].iter().all(|other| other.mint != leg.mint),
+            EscrowError::DuplicateMint
+        );
+    }
+    Ok(())
+}
+
+/// Splits `remaining` into one `[mint, source, destination]` chunk per leg.
+pub fn leg_accounts<'a, 'info>(
+    remaining: &'a [AccountInfo<'info>],
+    legs: usize,
+) -> Result<impl Iterator<Item = &'a [AccountInfo<'info>]>> {
+    require!(remaining.len() >= legs * LEG_ACCOUNTS, EscrowError::InvalidLegAccounts);
+    Ok(remaining[..legs * LEG_ACCOUNTS].chunks_exact(LEG_ACCOUNTS))
+}
+
+/// The transfer-hook accounts trailing the `legs` leg chunks.
+pub fn hook_accounts<'a, 'info>(remaining: &'a [AccountInfo<'info>], legs: usize) -> &'a [AccountInfo<'info>] {
+    remaining.get(legs * LEG_ACCOUNTS..).unwrap_or(&[])
+}
+
+/// Checks `mint` is the leg's mint under `token_program` and returns its decimals.
+pub fn leg_decimals(leg: &Leg, mint: &AccountInfo, token_program: &Pubkey) -> Result<u8> {
+    require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidLegAccounts);
+    require_keys_eq!(*mint.owner, *token_program, EscrowError::InvalidLegAccounts);
+    extensions::check_mint_extensions(mint)?;
+
+    let data = mint.try_borrow_data()?;
+    Ok(Mint::try_deserialize(&mut &data[..])?.decimals)
+}
+
+pub fn check_ata(ata: &AccountInfo, wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Result<()> {
+    require_keys_eq!(
+        ata.key(),
+        get_associated_token_address_with_program_id(wallet, mint, token_program),
+        EscrowError::InvalidLegAccounts
+    );
+    Ok(())
+}
+
+pub fn token_amount(account: &AccountInfo) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_leg<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+    hooks: &[AccountInfo<'info>],
+) -> Result<()> {
+    extensions::transfer_checked(token_program, from, mint, to, authority, amount, decimals, signer_seeds, hooks)
+}
+
+/// Empties an escrow-owned leg vault into `to` and closes it, rent to `rent_to`.
+#[allow(clippy::too_many_arguments)]
+pub fn release_leg<'info>(
+    token_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    escrow: &AccountInfo<'info>,
+    rent_to: &AccountInfo<'info>,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+    hooks: &[AccountInfo<'info>],
+) -> Result<()> {
+    let amount = token_amount(vault)?;
+    transfer_leg(token_program, vault, mint, to, escrow, amount, decimals, signer_seeds, hooks)?;
+    extensions::close_vault(token_program, mint, vault, rent_to, escrow, signer_seeds)
+}
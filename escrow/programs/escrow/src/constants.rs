@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+/// Extra mints a basket offer can carry on each side, on top of mint_a/mint_b.
+#[constant]
+pub const MAX_BASKET_LEGS: u8 = 4;
+
+/// Remaining accounts passed per basket leg: mint, source, destination.
+pub const LEG_ACCOUNTS: usize = 3;
+
+/// Open offers tracked per (mint_a, mint_b) order book.
+#[constant]
+pub const MAX_BOOK_ENTRIES: u8 = 64;
+
+/// Fixed-point scale of `BookEntry::price` (mint_b per unit of mint_a).
+#[constant]
+pub const PRICE_SCALE: u64 = 1_000_000_000;
+
+/// Upper bound on the protocol fee charged on takes, in basis points.
+#[constant]
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Upper bound on the lamports a maker can put up to reward whoever expires
+/// their offer.
+#[constant]
+pub const MAX_CRANK_REWARD_LAMPORTS: u64 = 10_000_000;
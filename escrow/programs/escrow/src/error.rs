@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("minimum fill cannot exceed the receive amount")]
+    InvalidMinFill,
+    #[msg("fill is below the maker's minimum fill size")]
+    FillTooSmall,
+    #[msg("fill exceeds the remaining receive amount")]
+    FillTooLarge,
+    #[msg("overflow on calculation")]
+    Overflow,
+    #[msg("offer has expired")]
+    OfferExpired,
+    #[msg("offer has not expired yet")]
+    OfferNotExpired,
+    #[msg("expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("crank reward exceeds MAX_CRANK_REWARD_LAMPORTS")]
+    InvalidCrankReward,
+    #[msg("offer is reserved for a different taker")]
+    TakerNotAllowed,
+    #[msg("too many basket legs")]
+    TooManyLegs,
+    #[msg("basket mints must be distinct")]
+    DuplicateMint,
+    #[msg("basket offers must be filled in full")]
+    BasketPartialFill,
+    #[msg("missing or mismatched basket leg accounts")]
+    InvalidLegAccounts,
+    #[msg("token accounts don't match the offer's SOL/SPL legs")]
+    TokenAccountMismatch,
+    #[msg("mint has a non-transferable or permanent-delegate extension")]
+    UnsupportedMintExtension,
+    #[msg("order book accounts don't match the requested mint")]
+    OrderBookMismatch,
+    #[msg("invalid fee amount")]
+    InvalidFeeAmount,
+    #[msg("signer is not the program's upgrade authority")]
+    Unauthorized,
+}
@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct OfferAmended {
+    pub escrow: Pubkey,
+    pub mint_b: Pubkey,
+    pub remaining_deposit: u64,
+    pub remaining_receive: u64,
+    pub min_fill: u64,
+}
@@ -0,0 +1,98 @@
+//! Token-2022 mint extension checks.
+
+use anchor_lang::{prelude::*, solana_program::program::invoke_signed};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::{instruction::harvest_withheld_tokens_to_mint, TransferFeeAmount},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    instruction::close_account,
+    onchain::invoke_transfer_checked,
+};
+
+use crate::error::EscrowError;
+
+/// Rejects mints the escrow can't safely hold: non-transferable tokens would be
+/// stuck in the vault, and a permanent delegate can drain it at any time.
+pub fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        require!(
+            !matches!(extension, ExtensionType::NonTransferable | ExtensionType::PermanentDelegate),
+            EscrowError::UnsupportedMintExtension
+        );
+    }
+    Ok(())
+}
+
+/// `transfer_checked` that resolves the mint's transfer hook, if it has one,
+/// from `hooks`. Anchor's helper only passes the four fixed accounts, so hook
+/// programs would never see their extra accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+    hooks: &[AccountInfo<'info>],
+) -> Result<()> {
+    invoke_transfer_checked(
+        token_program.key,
+        from.clone(),
+        mint.clone(),
+        to.clone(),
+        authority.clone(),
+        hooks,
+        amount,
+        decimals,
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+/// Closes an empty escrow-owned token account. Transfer fees withheld on it
+/// are harvested to the mint first, which needs the mint writable, since
+/// Token-2022 refuses to close an account still holding them.
+pub fn close_vault<'info>(
+    token_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if withheld_fees(vault)? > 0 {
+        let ix = harvest_withheld_tokens_to_mint(token_program.key, mint.key, &[vault.key])?;
+        invoke_signed(&ix, &[mint.clone(), vault.clone(), token_program.clone()], &[])?;
+    }
+
+    let ix = close_account(token_program.key, vault.key, destination.key, authority.key, &[])?;
+    invoke_signed(
+        &ix,
+        &[vault.clone(), destination.clone(), authority.clone(), token_program.clone()],
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+fn withheld_fees(account: &AccountInfo) -> Result<u64> {
+    if *account.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = account.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    Ok(state
+        .get_extension::<TransferFeeAmount>()
+        .map_or(0, |fees| u64::from(fees.withheld_amount)))
+}
@@ -0,0 +1,205 @@
+
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::*, token_interface::{TokenAccount,Mint,TokenInterface}};
+
+use crate::{basket, error::EscrowError, events::OfferAmended, extensions::{self, check_mint_extensions}, native, state::{Escrow, OrderBook}};
+
+#[derive(Accounts)]
+pub struct Amend<'info> {
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info,Mint>,
+
+    #[account(mint::token_program = token_program)]
+    pub new_mint_b: Option<InterfaceAccount<'info,Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one = mint_a,
+        has_one = maker,
+        seeds =[
+            b"escrow", 
+            maker.key().as_ref(), 
+            escrow.seed.to_le_bytes().as_ref()
+        ],
+        bump = escrow.bump, 
+    )]
+    pub escrow: Account<'info,Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"book", escrow.mint_a.as_ref(), escrow.mint_b.as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds = [b"book", mint_a.key().as_ref(), new_mint_b.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref()],
+        bump,
+        space = 8 + OrderBook::INIT_SPACE
+    )]
+    pub new_order_book: Option<Account<'info, OrderBook>>,
+
+    #[account(mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program)]
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
+
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program:Program<'info,System>,
+}
+
+impl<'info> Amend<'info> {
+    // Reprices what is still open: `receive_amount` is the new ask for the
+    // remaining deposit, and the requested mint can only change together with
+    // it and a `min_fill` in the new mint's units.
+    pub fn amend(&mut self, receive_amount: Option<u64>, min_fill: Option<u64>, top_up: u64, withdraw: u64, hooks: &[AccountInfo<'info>]) -> Result<()> {
+        require!(Clock::get()?.unix_timestamp <= self.escrow.expires_at, EscrowError::OfferExpired);
+        native::check_leg_accounts(
+            native::is_native_mint(&self.mint_a.key()),
+            &[self.maker_ata_a.is_some(), self.vault.is_some()],
+        )?;
+
+        require!(self.new_mint_b.is_some() == self.new_order_book.is_some(), EscrowError::OrderBookMismatch);
+        if let Some(new_mint_b) = &self.new_mint_b {
+            require!(receive_amount.is_some(), EscrowError::InvalidAmount);
+            require!(min_fill.is_some(), EscrowError::InvalidMinFill);
+            require_keys_neq!(new_mint_b.key(), self.escrow.mint_b, EscrowError::DuplicateMint);
+            basket::validate_legs(new_mint_b.key(), &self.escrow.extra_requests)?;
+            check_mint_extensions(&new_mint_b.to_account_info())?;
+            self.escrow.mint_b = new_mint_b.key();
+        }
+
+        if let Some(receive_amount) = receive_amount {
+            require!(receive_amount > 0, EscrowError::InvalidAmount);
+            let filled = self.escrow.receive_amount - self.escrow.remaining_receive;
+            self.escrow.receive_amount = filled.checked_add(receive_amount).ok_or(EscrowError::Overflow)?;
+            self.escrow.remaining_receive = receive_amount;
+        }
+
+        if top_up > 0 {
+            self.top_up(top_up, hooks)?;
+        }
+        if withdraw > 0 {
+            self.withdraw(withdraw, hooks)?;
+        }
+
+        let escrow = &mut self.escrow;
+        if let Some(min_fill) = min_fill {
+            require!(min_fill <= escrow.remaining_receive, EscrowError::InvalidMinFill);
+            escrow.min_fill = min_fill;
+        }
+        escrow.min_fill = if escrow.extra_deposits.is_empty() && escrow.extra_requests.is_empty() {
+            escrow.min_fill.min(escrow.remaining_receive)
+        } else {
+            escrow.remaining_receive
+        };
+
+        emit!(OfferAmended {
+            escrow: escrow.key(),
+            mint_b: escrow.mint_b,
+            remaining_deposit: escrow.remaining_deposit,
+            remaining_receive: escrow.remaining_receive,
+            min_fill: escrow.min_fill,
+        });
+        Ok(())
+    }
+
+    // Re-sorts the offer at its new price, moving it to the new pair's book
+    // when the requested mint changed.
+    pub fn relist_offer(&mut self, bumps: &AmendBumps) -> Result<()> {
+        let escrow = &self.escrow;
+        self.order_book.remove(&escrow.key());
+
+        let Some(book) = &mut self.new_order_book else {
+            self.order_book.upsert(escrow.key(), escrow);
+            return Ok(());
+        };
+        if book.mint_a == Pubkey::default() {
+            book.mint_a = escrow.mint_a;
+            book.mint_b = escrow.mint_b;
+            book.bump = bumps.new_order_book.ok_or(EscrowError::OrderBookMismatch)?;
+        }
+        book.upsert(escrow.key(), escrow);
+        Ok(())
+    }
+
+    pub fn top_up(&mut self, amount: u64, hooks: &[AccountInfo<'info>]) -> Result<()> {
+        let (Some(maker_ata_a), Some(vault)) = (&self.maker_ata_a, &mut self.vault) else {
+            self.escrow.remaining_deposit = self.escrow.remaining_deposit.checked_add(amount).ok_or(EscrowError::Overflow)?;
+            return native::transfer_lamports(
+                &self.system_program.to_account_info(),
+                &self.maker.to_account_info(),
+                &self.escrow.to_account_info(),
+                amount,
+            );
+        };
+        let before = vault.amount;
+
+        extensions::transfer_checked(
+            &self.token_program.to_account_info(),
+            &maker_ata_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &vault.to_account_info(),
+            &self.maker.to_account_info(),
+            amount,
+            self.mint_a.decimals,
+            &[],
+            hooks,
+        )?;
+
+        // Only count what landed in the vault, net of any transfer fee.
+        vault.reload()?;
+        let received = vault.amount - before;
+        self.escrow.remaining_deposit = self.escrow.remaining_deposit.checked_add(received).ok_or(EscrowError::Overflow)?;
+        Ok(())
+    }
+
+    // Partial withdrawals must leave something on offer; use `refund` to pull
+    // the whole deposit.
+    pub fn withdraw(&mut self, amount: u64, hooks: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount < self.escrow.remaining_deposit, EscrowError::InvalidAmount);
+        self.escrow.remaining_deposit -= amount;
+
+        let (Some(maker_ata_a), Some(vault)) = (&self.maker_ata_a, &self.vault) else {
+            self.escrow.sub_lamports(amount)?;
+            self.maker.add_lamports(amount)?;
+            return Ok(());
+        };
+
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+                b"escrow", 
+                self.maker.to_account_info().key.as_ref(),
+                &self.escrow.seed.to_le_bytes()[..],
+                &[self.escrow.bump]
+            ]];
+
+        extensions::transfer_checked(
+            &self.token_program.to_account_info(),
+            &vault.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &maker_ata_a.to_account_info(),
+            &self.escrow.to_account_info(),
+            amount,
+            self.mint_a.decimals,
+            &signer_seeds,
+            hooks,
+        )
+    }
+}
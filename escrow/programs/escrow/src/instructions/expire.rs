@@ -0,0 +1,162 @@
+
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::*, token_interface::{TokenAccount,Mint,TokenInterface}};
+
+use crate::{basket, error::EscrowError, extensions, native, state::{Escrow, OrderBook}};
+
+#[derive(Accounts)]
+pub struct Expire<'info> {
+
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    // Writable so transfer fees withheld on the vault can be harvested
+    // before it's closed.
+    #[account(mut, mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    #[account(
+        mut,
+        has_one = mint_a,
+        has_one = maker,
+        seeds =[
+            b"escrow", 
+            maker.key().as_ref(), 
+            escrow.seed.to_le_bytes().as_ref()
+        ],
+        bump = escrow.bump, 
+    )]
+    pub escrow: Account<'info,Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"book", escrow.mint_a.as_ref(), escrow.mint_b.as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program)]
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
+
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program:Program<'info,System>,
+}
+
+impl<'info> Expire<'info> {
+    pub fn return_and_close_vault(&mut self, remaining: &[AccountInfo<'info>]) -> Result<()> {
+        let hooks = basket::hook_accounts(remaining, self.escrow.extra_deposits.len());
+        require!(Clock::get()?.unix_timestamp > self.escrow.expires_at, EscrowError::OfferNotExpired);
+
+        native::check_leg_accounts(
+            native::is_native_mint(&self.mint_a.key()),
+            &[self.maker_ata_a.is_some(), self.vault.is_some()],
+        )?;
+        // A native SOL deposit goes back with the escrow's lamports on close.
+        let (Some(maker_ata_a), Some(vault)) = (&self.maker_ata_a, &self.vault) else {
+            return Ok(());
+        };
+
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+                b"escrow", 
+                self.maker.to_account_info().key.as_ref(),
+                &self.escrow.seed.to_le_bytes()[..],
+                &[self.escrow.bump]
+            ]];
+
+        extensions::transfer_checked(
+            &self.token_program.to_account_info(),
+            &vault.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &maker_ata_a.to_account_info(),
+            &self.escrow.to_account_info(),
+            vault.amount,
+            self.mint_a.decimals,
+            &signer_seeds,
+            hooks,
+        )?;
+
+        extensions::close_vault(
+            &self.token_program.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &vault.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.escrow.to_account_info(),
+            &signer_seeds,
+        )
+    }
+
+    // Hands every extra deposit leg back to the maker and closes its vault.
+    pub fn return_basket(&mut self, remaining: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = self.escrow.key();
+        let maker = self.maker.key();
+        let token_program = self.token_program.key();
+        let legs = self.escrow.extra_deposits.clone();
+        let hooks = basket::hook_accounts(remaining, legs.len());
+
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            b"escrow", 
+            maker.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump]
+        ]];
+
+        for (leg, accounts) in legs.iter().zip(basket::leg_accounts(remaining, legs.len())?) {
+            let [mint, vault, maker_ata] = accounts else {
+                return err!(EscrowError::InvalidLegAccounts);
+            };
+            let decimals = basket::leg_decimals(leg, mint, &token_program)?;
+            basket::check_ata(vault, &escrow, &leg.mint, &token_program)?;
+            basket::check_ata(maker_ata, &maker, &leg.mint, &token_program)?;
+
+            basket::release_leg(
+                &self.token_program.to_account_info(),
+                mint,
+                vault,
+                maker_ata,
+                &self.escrow.to_account_info(),
+                &self.maker.to_account_info(),
+                decimals,
+                &signer_seeds,
+                hooks,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn pay_crank_reward(&mut self) -> Result<()> {
+        let reward = self.escrow.crank_reward;
+        if reward == 0 {
+            return Ok(());
+        }
+
+        self.escrow.sub_lamports(reward)?;
+        self.cranker.add_lamports(reward)?;
+        Ok(())
+    }
+
+    pub fn close_escrow(&mut self) -> Result<()> {
+        self.escrow.close(self.maker.to_account_info())
+    }
+
+    pub fn delist_offer(&mut self) -> Result<()> {
+        self.order_book.remove(&self.escrow.key());
+        Ok(())
+    }
+}
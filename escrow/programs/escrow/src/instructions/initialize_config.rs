@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_FEE_BPS, error::EscrowError, program::Escrow, state::Config};
+
+// Only the program's upgrade authority can create the config and name its
+// first admin.
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ EscrowError::Unauthorized)]
+    pub program: Program<'info, Escrow>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ EscrowError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"config"],
+        bump,
+        space = 8 + Config::INIT_SPACE
+    )]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeConfig<'info> {
+    pub fn initialize_config(&mut self, fee_bps: u16, treasury: Pubkey, bumps: &InitializeConfigBumps) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, EscrowError::InvalidFeeAmount);
+
+        self.config.set_inner(Config {
+            admin: self.admin.key(),
+            treasury,
+            fee_bps,
+            bump: bumps.config,
+        });
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
 use anchor_lang::prelude::*;
 
-use anchor_spl::{associated_token::*, token_interface::{TokenAccount,Mint,TokenInterface,TransferChecked, transfer_checked}};
+use anchor_spl::{associated_token::{create, AssociatedToken, Create}, token_interface::{TokenAccount,Mint,TokenInterface}};
 
-use crate::state::Escrow;
+use crate::{basket, error::EscrowError, extensions::{self, check_mint_extensions}, native, state::{Escrow, Leg, OrderBook}, MAX_CRANK_REWARD_LAMPORTS};
 
 
 #[derive(Accounts)]
@@ -24,7 +24,7 @@ pub struct Make<'info> {
         associated_token::authority = maker,
         associated_token::token_program = token_program
     )]
-    pub maker_ata_a: InterfaceAccount<'info,TokenAccount>,
+    pub maker_ata_a: Option<InterfaceAccount<'info,TokenAccount>>,
 
 
     #[account(
@@ -38,11 +38,20 @@ pub struct Make<'info> {
     )]
     pub escrow: Account<'info,Escrow>,
 
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds = [b"book", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump,
+        space = 8 + OrderBook::INIT_SPACE
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
     #[account(init, payer = maker,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
         associated_token::token_program = token_program)]
-    pub vault: InterfaceAccount<'info,TokenAccount>,
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
 
 
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -56,43 +65,139 @@ pub struct Make<'info> {
 
 
 impl<'info> Make<'info> {
-    pub fn init_escrow(&mut self, seed: u64, recieve: u64, bumps: &MakeBumps) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_escrow(&mut self, seed: u64, deposit: u64, recieve: u64, min_fill: u64, expires_at: i64, crank_reward: u64, allowed_taker: Option<Pubkey>, extra_deposits: Vec<Leg>, extra_requests: Vec<Leg>, bumps: &MakeBumps) -> Result<()> {
+        require!(deposit > 0 && recieve > 0, EscrowError::InvalidAmount);
+        require!(min_fill <= recieve, EscrowError::InvalidMinFill);
+        require!(expires_at > Clock::get()?.unix_timestamp, EscrowError::InvalidExpiry);
+        require!(crank_reward <= MAX_CRANK_REWARD_LAMPORTS, EscrowError::InvalidCrankReward);
+        native::check_leg_accounts(
+            native::is_native_mint(&self.mint_a.key()),
+            &[self.maker_ata_a.is_some(), self.vault.is_some()],
+        )?;
+        check_mint_extensions(&self.mint_a.to_account_info())?;
+        check_mint_extensions(&self.mint_b.to_account_info())?;
+
+        basket::validate_legs(self.mint_a.key(), &extra_deposits)?;
+        basket::validate_legs(self.mint_b.key(), &extra_requests)?;
+        if !extra_deposits.is_empty() || !extra_requests.is_empty() {
+            require!(min_fill == recieve, EscrowError::BasketPartialFill);
+        }
+
         self.escrow.set_inner(Escrow{
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
             receive_amount: recieve,
+            remaining_deposit: deposit,
+            remaining_receive: recieve,
+            min_fill,
+            expires_at,
+            crank_reward,
+            allowed_taker,
+            extra_deposits,
+            extra_requests,
             bump: bumps.escrow,
         });
         Ok(())
     }
 
-    pub fn deposit(&mut self, deposit: u64) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
-        let transfer_accounts = TransferChecked {
-            from: self.maker_ata_a.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            to: self.vault.to_account_info(),
-            authority: self.maker.to_account_info(),
+    // The reward sits on the escrow PDA next to its rent; it goes back to the
+    // maker on take/refund and to whoever cranks `expire` otherwise.
+    pub fn fund_crank_reward(&mut self, crank_reward: u64) -> Result<()> {
+        if crank_reward == 0 {
+            return Ok(());
+        }
+
+        native::transfer_lamports(
+            &self.system_program.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.escrow.to_account_info(),
+            crank_reward,
+        )
+    }
+
+    // A native SOL deposit is parked on the escrow PDA itself.
+    pub fn deposit(&mut self, deposit: u64, remaining: &[AccountInfo<'info>]) -> Result<()> {
+        let hooks = basket::hook_accounts(remaining, self.escrow.extra_deposits.len());
+        let (Some(maker_ata_a), Some(vault)) = (&self.maker_ata_a, &mut self.vault) else {
+            return native::transfer_lamports(
+                &self.system_program.to_account_info(),
+                &self.maker.to_account_info(),
+                &self.escrow.to_account_info(),
+                deposit,
+            );
         };
-        let cpi_ctx = CpiContext::new(cpi_program, transfer_accounts);
 
-        transfer_checked(cpi_ctx, deposit, self.mint_a.decimals)
+        extensions::transfer_checked(
+            &self.token_program.to_account_info(),
+            &maker_ata_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &vault.to_account_info(),
+            &self.maker.to_account_info(),
+            deposit,
+            self.mint_a.decimals,
+            &[],
+            hooks,
+        )?;
+
+        // Transfer-fee mints withhold part of the deposit; only what actually
+        // landed in the vault is on offer.
+        vault.reload()?;
+        self.escrow.remaining_deposit = vault.amount;
+        Ok(())
     }
 
-    pub fn withdraw(&mut self, amount: u64) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
-
-        let transfer_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
+    // Opens an escrow-owned vault for every extra deposit leg and funds it.
+    pub fn deposit_basket(&mut self, remaining: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = self.escrow.key();
+        let token_program = self.token_program.key();
+        let legs = self.escrow.extra_deposits.clone();
+        let hooks = basket::hook_accounts(remaining, legs.len());
+
+        for (leg, accounts) in legs.iter().zip(basket::leg_accounts(remaining, legs.len())?) {
+            let [mint, maker_ata, vault] = accounts else {
+                return err!(EscrowError::InvalidLegAccounts);
+            };
+            let decimals = basket::leg_decimals(leg, mint, &token_program)?;
+            basket::check_ata(vault, &escrow, &leg.mint, &token_program)?;
+
+            create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.maker.to_account_info(),
+                    associated_token: vault.clone(),
+                    authority: self.escrow.to_account_info(),
+                    mint: mint.clone(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                },
+            ))?;
+
+            basket::transfer_leg(
+                &self.token_program.to_account_info(),
+                maker_ata,
+                mint,
+                vault,
+                &self.maker.to_account_info(),
+                leg.amount,
+                decimals,
+                &[],
+                hooks,
+            )?;
+        }
+        Ok(())
+    }
 
-        let cpi_ctx = CpiContext::new(cpi_program, transfer_accounts);
-        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+    pub fn list_offer(&mut self, bumps: &MakeBumps) -> Result<()> {
+        let book = &mut self.order_book;
+        if book.mint_a == Pubkey::default() {
+            book.mint_a = self.mint_a.key();
+            book.mint_b = self.mint_b.key();
+            book.bump = bumps.order_book;
+        }
+        book.upsert(self.escrow.key(), &self.escrow);
+        Ok(())
     }
 }
-
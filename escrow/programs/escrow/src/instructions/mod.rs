@@ -1,3 +1,12 @@
+pub mod amend;
+pub use amend::*;
+
+pub mod expire;
+pub use expire::*;
+
+pub mod initialize_config;
+pub use initialize_config::*;
+
 pub mod make;
 pub use make::*;
 
@@ -5,4 +14,7 @@ pub mod refund;
 pub use refund::*;
 
 pub mod take;
-pub use take::*;
\ No newline at end of file
+pub use take::*;
+
+pub mod update_config;
+pub use update_config::*;
\ No newline at end of file
@@ -1,19 +1,20 @@
 
 use anchor_lang::prelude::*;
 
-use anchor_spl::{associated_token::*, token_interface::{TokenAccount,Mint,TokenInterface,TransferChecked, transfer_checked, CloseAccount, close_account}};
+use anchor_spl::{associated_token::{create_idempotent, AssociatedToken, Create}, token_interface::{TokenAccount,Mint,TokenInterface}};
 
-use crate::state::Escrow;
+use crate::{basket, error::EscrowError, extensions, native, state::{Config, Escrow, OrderBook}};
 #[derive(Accounts)]
-#[instruction(seed: u64)]
 pub struct Take<'info> {
     #[account(mut)]
     pub taker: Signer<'info>,
 
     #[account(mut)]
-    pub maker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
 
-    #[account(mint::token_program = token_program)]
+    // Writable so transfer fees withheld on the vault can be harvested
+    // before it's closed.
+    #[account(mut, mint::token_program = token_program)]
     pub mint_a: InterfaceAccount<'info,Mint>,
     
     #[account(mint::token_program = token_program)]
@@ -26,7 +27,7 @@ pub struct Take<'info> {
         associated_token::authority = taker,
         associated_token::token_program = token_program
     )]
-    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub taker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
@@ -35,7 +36,7 @@ pub struct Take<'info> {
         associated_token::authority = taker,
         associated_token::token_program = token_program
     )]
-    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    pub taker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
     
     #[account(
         init_if_needed,
@@ -44,23 +45,55 @@ pub struct Take<'info> {
         associated_token::authority = maker,
         associated_token::token_program = token_program
     )]
-    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // Every take reads the protocol fee, so `config` and `treasury` are
+    // required; clients from before the fee existed have to start passing
+    // them. `treasury_ata_b` can be left out while the fee is zero.
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = treasury,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program
+    )]
+    pub treasury_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut, 
+        has_one = maker,
+        has_one = mint_a,
+        has_one = mint_b,
+        constraint = escrow.allowed_taker.is_none_or(|allowed| allowed == taker.key()) @ EscrowError::TakerNotAllowed,
         seeds =[b"escrow", 
         maker.key().as_ref(), 
-        seed.to_le_bytes().as_ref()],
-        bump,
-        close = maker,
+        escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
     )]
     pub escrow: Account<'info,Escrow>,
 
+    #[account(
+        mut,
+        seeds = [b"book", escrow.mint_a.as_ref(), escrow.mint_b.as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
     #[account(mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
         associated_token::token_program = token_program)]
-    pub vault: InterfaceAccount<'info,TokenAccount>,
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
 
 
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -74,22 +107,102 @@ pub struct Take<'info> {
 
 
 impl<'info> Take<'info> {
-    
-    pub fn deposit(&mut self, amount: u64) -> Result<()> {
-
-        let cpi_program = self.token_program.to_account_info();
-        let transfer_accounts = TransferChecked {
-            from: self.taker_ata_b.to_account_info(),
-            mint: self.mint_b.to_account_info(),
-            to: self.maker_ata_b.to_account_info(),
-            authority: self.taker.to_account_info(),
+
+    pub fn check_leg_accounts(&self) -> Result<()> {
+        native::check_leg_accounts(
+            native::is_native_mint(&self.mint_a.key()),
+            &[self.taker_ata_a.is_some(), self.vault.is_some()],
+        )?;
+        // The treasury's token account can be left out while there's no fee.
+        let mut present = vec![self.taker_ata_b.is_some(), self.maker_ata_b.is_some()];
+        if self.config.fee_bps > 0 {
+            present.push(self.treasury_ata_b.is_some());
+        }
+        native::check_leg_accounts(native::is_native_mint(&self.mint_b.key()), &present)
+    }
+
+    fn hook_accounts<'a>(&self, remaining: &'a [AccountInfo<'info>]) -> &'a [AccountInfo<'info>] {
+        let legs = self.escrow.extra_deposits.len() + self.escrow.extra_requests.len();
+        basket::hook_accounts(remaining, legs)
+    }
+
+    // Books a fill of `amount` mint_b against the offer and returns the
+    // pro-rata share of mint_a it releases. The last fill takes whatever is
+    // left in the vault so rounding never strands dust.
+    pub fn fill(&mut self, amount: u64) -> Result<u64> {
+        let escrow = &mut self.escrow;
+
+        require!(Clock::get()?.unix_timestamp <= escrow.expires_at, EscrowError::OfferExpired);
+        require!(amount > 0, EscrowError::InvalidAmount);
+        require!(amount <= escrow.remaining_receive, EscrowError::FillTooLarge);
+
+        let release = if amount == escrow.remaining_receive {
+            escrow.remaining_deposit
+        } else {
+            require!(amount >= escrow.min_fill, EscrowError::FillTooSmall);
+            u64::try_from(
+                (amount as u128)
+                    .checked_mul(escrow.remaining_deposit as u128)
+                    .ok_or(EscrowError::Overflow)?
+                    / escrow.remaining_receive as u128,
+            )
+            .map_err(|_| EscrowError::Overflow)?
+        };
+        require!(release > 0, EscrowError::FillTooSmall);
+
+        escrow.remaining_receive -= amount;
+        escrow.remaining_deposit -= release;
+        Ok(release)
+    }
+
+    // The protocol fee comes out of the taker's payment and the maker gets
+    // the rest, so fills still count in full against `remaining_receive`.
+    pub fn deposit(&mut self, amount: u64, remaining: &[AccountInfo<'info>]) -> Result<()> {
+        let hooks = self.hook_accounts(remaining);
+        let fee = self.config.fee_on(amount)?;
+
+        let (Some(taker_ata_b), Some(maker_ata_b)) = (&self.taker_ata_b, &self.maker_ata_b) else {
+            let system_program = self.system_program.to_account_info();
+            let taker = self.taker.to_account_info();
+            if fee > 0 {
+                native::transfer_lamports(&system_program, &taker, &self.treasury.to_account_info(), fee)?;
+            }
+            return native::transfer_lamports(&system_program, &taker, &self.maker.to_account_info(), amount - fee);
         };
-        let cpi_ctx = CpiContext::new(cpi_program, transfer_accounts);
 
-        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)
+        let mut payouts = vec![(maker_ata_b, amount - fee)];
+        if fee > 0 {
+            let treasury_ata_b = self.treasury_ata_b.as_ref().ok_or(EscrowError::TokenAccountMismatch)?;
+            payouts.push((treasury_ata_b, fee));
+        }
+        for (to, share) in payouts {
+            if share == 0 {
+                continue;
+            }
+
+            extensions::transfer_checked(
+                &self.token_program.to_account_info(),
+                &taker_ata_b.to_account_info(),
+                &self.mint_b.to_account_info(),
+                &to.to_account_info(),
+                &self.taker.to_account_info(),
+                share,
+                self.mint_b.decimals,
+                &[],
+                hooks,
+            )?;
+        }
+        Ok(())
     }
     
-    pub fn withdraw(&mut self) -> Result<()>{
+    pub fn withdraw(&mut self, amount: u64, remaining: &[AccountInfo<'info>]) -> Result<()>{
+        let hooks = self.hook_accounts(remaining);
+        let (Some(vault), Some(taker_ata_a)) = (&self.vault, &self.taker_ata_a) else {
+            self.escrow.sub_lamports(amount)?;
+            self.taker.add_lamports(amount)?;
+            return Ok(());
+        };
+
         let signer_seeds: [&[&[u8]] ;1] = [&[
                 b"escrow", 
                 self.maker.to_account_info().key.as_ref(),
@@ -97,21 +210,23 @@ impl<'info> Take<'info> {
                 &[self.escrow.bump]
             ]];
 
-        let cpi_program = self.token_program.to_account_info();
-        
-        let transfer_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            to: self.taker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
-        
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, transfer_accounts, &signer_seeds);
-        
-        transfer_checked(cpi_ctx, self.vault.amount, self.mint_a.decimals)
+        extensions::transfer_checked(
+            &self.token_program.to_account_info(),
+            &vault.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &taker_ata_a.to_account_info(),
+            &self.escrow.to_account_info(),
+            amount,
+            self.mint_a.decimals,
+            &signer_seeds,
+            hooks,
+        )
     }
 
     pub fn close_vault(&mut self)  -> Result<()> {
+        let Some(vault) = &self.vault else {
+            return Ok(());
+        };
 
         let signer_seeds: [&[&[u8]] ;1] = [&[
             b"escrow", 
@@ -120,13 +235,100 @@ impl<'info> Take<'info> {
             &[self.escrow.bump]
         ]];
 
-        let close_accounts = CloseAccount {
-            account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
-            authority: self.escrow.to_account_info()
-        };
+        extensions::close_vault(
+            &self.token_program.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &vault.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.escrow.to_account_info(),
+            &signer_seeds,
+        )
+    }
+
+    // Pays every extra request leg to the maker and releases every extra
+    // deposit leg to the taker. Only runs on the final fill, and basket offers
+    // can't be partially filled, so this is all-or-nothing with the trade.
+    pub fn settle_basket(&mut self, remaining: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = self.escrow.key();
+        let maker = self.maker.key();
+        let token_program = self.token_program.key();
+        let deposits = self.escrow.extra_deposits.clone();
+        let requests = self.escrow.extra_requests.clone();
+
+        let mut chunks = basket::leg_accounts(remaining, deposits.len() + requests.len())?;
+        let hooks = self.hook_accounts(remaining);
+
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            b"escrow", 
+            maker.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump]
+        ]];
+
+        for leg in deposits.iter() {
+            let Some([mint, vault, taker_ata]) = chunks.next() else {
+                return err!(EscrowError::InvalidLegAccounts);
+            };
+            let decimals = basket::leg_decimals(leg, mint, &token_program)?;
+            basket::check_ata(vault, &escrow, &leg.mint, &token_program)?;
+
+            basket::release_leg(
+                &self.token_program.to_account_info(),
+                mint,
+                vault,
+                taker_ata,
+                &self.escrow.to_account_info(),
+                &self.maker.to_account_info(),
+                decimals,
+                &signer_seeds,
+                hooks,
+            )?;
+        }
+
+        for leg in requests.iter() {
+            let Some([mint, taker_ata, maker_ata]) = chunks.next() else {
+                return err!(EscrowError::InvalidLegAccounts);
+            };
+            let decimals = basket::leg_decimals(leg, mint, &token_program)?;
+            basket::check_ata(maker_ata, &maker, &leg.mint, &token_program)?;
+
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.taker.to_account_info(),
+                    associated_token: maker_ata.clone(),
+                    authority: self.maker.to_account_info(),
+                    mint: mint.clone(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                },
+            ))?;
+
+            basket::transfer_leg(
+                &self.token_program.to_account_info(),
+                taker_ata,
+                mint,
+                maker_ata,
+                &self.taker.to_account_info(),
+                leg.amount,
+                decimals,
+                &[],
+                hooks,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn update_order_book(&mut self) -> Result<()> {
+        if self.escrow.remaining_receive == 0 {
+            self.order_book.remove(&self.escrow.key());
+            return Ok(());
+        }
+        self.order_book.upsert(self.escrow.key(), &self.escrow);
+        Ok(())
+    }
 
-        let close_cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), close_accounts, &signer_seeds);
-        close_account(close_cpi_ctx)
+    pub fn close_escrow(&mut self) -> Result<()> {
+        self.escrow.close(self.maker.to_account_info())
     }
 }
\ No newline at end of file
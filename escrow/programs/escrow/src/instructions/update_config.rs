@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::MAX_FEE_BPS, error::EscrowError, state::Config};
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    pub fn update_config(&mut self, fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, EscrowError::InvalidFeeAmount);
+
+        self.config.fee_bps = fee_bps;
+        self.config.treasury = treasury;
+        Ok(())
+    }
+}
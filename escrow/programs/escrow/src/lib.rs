@@ -2,40 +2,70 @@ use anchor_lang::prelude::*;
 
 declare_id!("6Qs5hWd41Fz3js2sdnHfQ6h2R7AMJ9GAfPaqC8UYbDwm");
 
+pub mod basket;
+pub mod constants;
+pub mod error;
+pub mod events;
+pub mod extensions;
 pub mod instructions;
+pub mod native;
 pub mod state;
+pub use constants::*;
 pub use instructions::*;
+pub use state::Leg;
 
 #[program]
 pub mod escrow {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        msg!("Greetings from: {:?}", ctx.program_id);
-        Ok(())
+    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        ctx.accounts.initialize_config(fee_bps, treasury, &ctx.bumps)
     }
 
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, recieve: u64, bumps_in: u8 ) -> Result<()>{
-        let bumps: MakeBumps = MakeBumps {
-            escrow: bumps_in
-        };
+    pub fn update_config(ctx: Context<UpdateConfig>, fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        ctx.accounts.update_config(fee_bps, treasury)
+    }
 
-        let _ = ctx.accounts.init_escrow(seed, recieve, &bumps);
-        ctx.accounts.deposit(deposit)
+    #[allow(clippy::too_many_arguments)]
+    pub fn make<'info>(ctx: Context<'_, '_, '_, 'info, Make<'info>>, seed: u64, deposit: u64, recieve: u64, min_fill: u64, expires_at: i64, crank_reward: u64, allowed_taker: Option<Pubkey>, extra_deposits: Vec<Leg>, extra_requests: Vec<Leg>) -> Result<()>{
+        ctx.accounts.init_escrow(seed, deposit, recieve, min_fill, expires_at, crank_reward, allowed_taker, extra_deposits, extra_requests, &ctx.bumps)?;
+        ctx.accounts.fund_crank_reward(crank_reward)?;
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)?;
+        ctx.accounts.deposit_basket(ctx.remaining_accounts)?;
+        ctx.accounts.list_offer(&ctx.bumps)
     }
 
-    pub fn take (ctx: Context<Take>, amount: u64) -> Result<()>{
-        let _ = ctx.accounts.deposit(amount);
-        let _ = ctx.accounts.withdraw();
-        ctx.accounts.close_vault()
+    pub fn take<'info>(ctx: Context<'_, '_, '_, 'info, Take<'info>>, amount: u64) -> Result<()>{
+        ctx.accounts.check_leg_accounts()?;
+        let release = ctx.accounts.fill(amount)?;
+        ctx.accounts.deposit(amount, ctx.remaining_accounts)?;
+        ctx.accounts.withdraw(release, ctx.remaining_accounts)?;
+        ctx.accounts.update_order_book()?;
+
+        if ctx.accounts.escrow.remaining_receive == 0 {
+            ctx.accounts.settle_basket(ctx.remaining_accounts)?;
+            ctx.accounts.close_vault()?;
+            ctx.accounts.close_escrow()?;
+        }
+        Ok(())
     }
 
-    pub fn refund (ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund_and_close_vault()
+    pub fn refund<'info>(ctx: Context<'_, '_, '_, 'info, Refund<'info>>) -> Result<()> {
+        ctx.accounts.refund_basket(ctx.remaining_accounts)?;
+        ctx.accounts.delist_offer()?;
+        ctx.accounts.refund_and_close_vault(ctx.remaining_accounts)
     }
 
+    pub fn amend<'info>(ctx: Context<'_, '_, '_, 'info, Amend<'info>>, receive_amount: Option<u64>, min_fill: Option<u64>, top_up: u64, withdraw: u64) -> Result<()> {
+        ctx.accounts.amend(receive_amount, min_fill, top_up, withdraw, ctx.remaining_accounts)?;
+        ctx.accounts.relist_offer(&ctx.bumps)
+    }
 
+    pub fn expire<'info>(ctx: Context<'_, '_, '_, 'info, Expire<'info>>) -> Result<()> {
+        ctx.accounts.return_and_close_vault(ctx.remaining_accounts)?;
+        ctx.accounts.return_basket(ctx.remaining_accounts)?;
+        ctx.accounts.delist_offer()?;
+        ctx.accounts.pay_crank_reward()?;
+        ctx.accounts.close_escrow()
+    }
 }
-
-#[derive(Accounts)]
-pub struct Initialize {}
@@ -0,0 +1,36 @@
+//! Helpers for offers with a native SOL leg.
+//!
+//! A leg is native when its mint is one of the token programs' native mints.
+//! Such a leg settles in lamports held directly on the escrow PDA, so its
+//! token accounts (vault and the parties' ATAs) are passed as `None`.
+
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{token::spl_token, token_2022::spl_token_2022};
+
+use crate::error::EscrowError;
+
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+/// SPL legs need all of their token accounts, native legs must get none.
+pub fn check_leg_accounts(native: bool, present: &[bool]) -> Result<()> {
+    require!(present.iter().all(|&p| p != native), EscrowError::TokenAccountMismatch);
+    Ok(())
+}
+
+pub fn transfer_lamports<'info>(
+    system_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_ctx = CpiContext::new(
+        system_program.clone(),
+        Transfer {
+            from: from.clone(),
+            to: to.clone(),
+        },
+    );
+    transfer(cpi_ctx, amount)
+}
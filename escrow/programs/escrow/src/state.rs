@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 
+use crate::{constants::{MAX_BASKET_LEGS, MAX_BOOK_ENTRIES, PRICE_SCALE}, error::EscrowError};
 
 #[account]
 #[derive(InitSpace)]
@@ -9,5 +10,98 @@ pub struct Escrow {
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
     pub receive_amount: u64,
+    pub remaining_deposit: u64,
+    pub remaining_receive: u64,
+    pub min_fill: u64,
+    pub expires_at: i64,
+    pub crank_reward: u64,
+    pub allowed_taker: Option<Pubkey>,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub extra_deposits: Vec<Leg>,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub extra_requests: Vec<Leg>,
     pub bump: u8,
-}
\ No newline at end of file
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Leg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub bump: u8,
+}
+
+impl Config {
+    pub fn fee_on(&self, amount: u64) -> Result<u64> {
+        let fee = (amount as u128)
+            .checked_mul(self.fee_bps as u128)
+            .ok_or(EscrowError::Overflow)?
+            / 10_000;
+        Ok(fee as u64)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OrderBook {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    #[max_len(MAX_BOOK_ENTRIES)]
+    pub entries: Vec<BookEntry>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct BookEntry {
+    pub escrow: Pubkey,
+    pub price: u64,
+    pub remaining_deposit: u64,
+    pub remaining_receive: u64,
+}
+
+impl BookEntry {
+    pub fn new(escrow: Pubkey, offer: &Escrow) -> Self {
+        let price = (offer.remaining_receive as u128 * PRICE_SCALE as u128)
+            .checked_div(offer.remaining_deposit as u128)
+            .map_or(u64::MAX, |price| u64::try_from(price).unwrap_or(u64::MAX));
+
+        Self {
+            escrow,
+            price,
+            remaining_deposit: offer.remaining_deposit,
+            remaining_receive: offer.remaining_receive,
+        }
+    }
+}
+
+// Entries are kept sorted cheapest-first, so `entries[0]` is the best offer.
+impl OrderBook {
+    // A full book keeps its cheapest offers: a better price evicts the worst
+    // entry, anything else goes unindexed. An unindexed offer can still be
+    // taken by anyone who knows its address.
+    pub fn upsert(&mut self, escrow: Pubkey, offer: &Escrow) {
+        self.remove(&escrow);
+
+        let entry = BookEntry::new(escrow, offer);
+        if self.entries.len() >= MAX_BOOK_ENTRIES as usize {
+            if self.entries.last().is_some_and(|worst| worst.price <= entry.price) {
+                return;
+            }
+            self.entries.pop();
+        }
+
+        let at = self.entries.partition_point(|other| other.price <= entry.price);
+        self.entries.insert(at, entry);
+    }
+
+    pub fn remove(&mut self, escrow: &Pubkey) {
+        self.entries.retain(|entry| entry.escrow != *escrow);
+    }
+}
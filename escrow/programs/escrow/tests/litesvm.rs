@@ -0,0 +1,576 @@
+//! In-process tests for make/take/refund on top of LiteSVM.
+//!
+//! These load the compiled program from `target/deploy`, so they're ignored
+//! by a plain `cargo test`. Run them with `anchor build && cargo test --
+//! --ignored`; they fail if the program hasn't been built.
+
+use anchor_lang::{solana_program::{instruction::{AccountMeta, Instruction}, system_program}, InstructionData, ToAccountMetas};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token::spl_token,
+    token_2022::spl_token_2022::{self, extension::StateWithExtensions, state::Account as TokenAccount},
+};
+use escrow::{
+    constants::MAX_BOOK_ENTRIES,
+    error::EscrowError,
+    state::{Escrow, Leg, OrderBook},
+};
+use litesvm::LiteSVM;
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable,
+    clock::Clock,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const DECIMALS: u8 = 6;
+const MINTED: u64 = 1_000_000_000;
+const DEPOSIT: u64 = 1_000;
+const RECEIVE: u64 = 500;
+const MIN_FILL: u64 = 100;
+const SEED: u64 = 42;
+const PROGRAM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy/escrow.so");
+
+struct Setup {
+    svm: LiteSVM,
+    payer: Keypair,
+    maker: Keypair,
+    taker: Keypair,
+    treasury: Pubkey,
+    token_program: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    maker_ata_a: Pubkey,
+    taker_ata_b: Pubkey,
+    escrow: Pubkey,
+    order_book: Pubkey,
+    vault: Pubkey,
+}
+
+impl Setup {
+    fn new(token_program: Pubkey) -> Self {
+        let mut setup = Self::deployed(token_program);
+        let payer = setup.payer.insecure_clone();
+        setup.initialize_config(&payer).unwrap();
+        setup
+    }
+
+    // Deploys the program with `payer` as its upgrade authority, without a config.
+    fn deployed(token_program: Pubkey) -> Self {
+        let program = std::fs::read(PROGRAM)
+            .unwrap_or_else(|err| panic!("can't read {PROGRAM} ({err}), run `anchor build` first"));
+        let mut svm = LiteSVM::new();
+        let payer = Keypair::new();
+        deploy_upgradeable(&mut svm, &program, &payer.pubkey());
+
+        let maker = Keypair::new();
+        let taker = Keypair::new();
+        for wallet in [&payer, &maker, &taker] {
+            svm.airdrop(&wallet.pubkey(), 10_000_000_000).unwrap();
+        }
+
+        let mint_a = CreateMint::new(&mut svm, &payer)
+            .decimals(DECIMALS)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let mint_b = CreateMint::new(&mut svm, &payer)
+            .decimals(DECIMALS)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &payer, &mint_a)
+            .owner(&maker.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &payer, &mint_b)
+            .owner(&taker.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut svm, &payer, &mint_a, &maker_ata_a, MINTED)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut svm, &payer, &mint_b, &taker_ata_b, MINTED)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let (escrow, _) = Pubkey::find_program_address(
+            &[b"escrow", maker.pubkey().as_ref(), &SEED.to_le_bytes()],
+            &escrow::ID,
+        );
+        let (order_book, _) = Pubkey::find_program_address(
+            &[b"book", mint_a.as_ref(), mint_b.as_ref()],
+            &escrow::ID,
+        );
+        let vault = get_associated_token_address_with_program_id(&escrow, &mint_a, &token_program);
+
+        Self {
+            svm,
+            payer,
+            maker,
+            taker,
+            treasury: Pubkey::new_unique(),
+            token_program,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            taker_ata_b,
+            escrow,
+            order_book,
+            vault,
+        }
+    }
+
+    fn config(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"config"], &escrow::ID).0
+    }
+
+    fn initialize_config(&mut self, admin: &Keypair) -> Result<(), Option<u32>> {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::InitializeConfig {
+                admin: admin.pubkey(),
+                program: escrow::ID,
+                program_data: program_data(),
+                config: self.config(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::InitializeConfig {
+                fee_bps: 0,
+                treasury: self.treasury,
+            }
+            .data(),
+        };
+        self.try_send(ix, &[admin])
+    }
+
+    fn ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program)
+    }
+
+    fn send(&mut self, ix: Instruction, signers: &[&Keypair]) {
+        self.try_send(ix, signers).unwrap();
+    }
+
+    // The custom error code the transaction failed with, if any.
+    fn try_send(&mut self, ix: Instruction, signers: &[&Keypair]) -> Result<(), Option<u32>> {
+        let signers: Vec<&Keypair> = std::iter::once(&self.payer).chain(signers.iter().copied()).collect();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.payer.pubkey()),
+            &signers,
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(tx).map(|_| ()).map_err(|failed| match failed.err {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+            _ => None,
+        })
+    }
+
+    fn make(&mut self) {
+        self.make_offer(SEED, RECEIVE);
+    }
+
+    fn escrow_for(&self, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"escrow", self.maker.pubkey().as_ref(), &seed.to_le_bytes()],
+            &escrow::ID,
+        )
+        .0
+    }
+
+    fn make_offer(&mut self, seed: u64, receive: u64) {
+        let ix = self.make_ix(seed, receive, vec![], vec![]);
+        let maker = self.maker.insecure_clone();
+        self.send(ix, &[&maker]);
+    }
+
+    // Basket legs go in as remaining accounts, so callers append those.
+    fn make_ix(&self, seed: u64, receive: u64, extra_deposits: Vec<Leg>, extra_requests: Vec<Leg>) -> Instruction {
+        let escrow = self.escrow_for(seed);
+        let expires_at = self.svm.get_sysvar::<Clock>().unix_timestamp + 3_600;
+        Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::Make {
+                maker: self.maker.pubkey(),
+                mint_a: self.mint_a,
+                mint_b: self.mint_b,
+                maker_ata_a: Some(self.maker_ata_a),
+                escrow,
+                order_book: self.order_book,
+                vault: Some(self.ata(&escrow, &self.mint_a)),
+                associated_token_program: associated_token::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::Make {
+                seed,
+                deposit: DEPOSIT,
+                recieve: receive,
+                min_fill: MIN_FILL,
+                expires_at,
+                crank_reward: 0,
+                allowed_taker: None,
+                extra_deposits,
+                extra_requests,
+            }
+            .data(),
+        }
+    }
+
+    fn take(&mut self) {
+        self.try_take(RECEIVE).unwrap();
+    }
+
+    fn try_take(&mut self, amount: u64) -> Result<(), Option<u32>> {
+        let ix = self.take_ix(amount);
+        let taker = self.taker.insecure_clone();
+        self.try_send(ix, &[&taker])
+    }
+
+    fn take_ix(&self, amount: u64) -> Instruction {
+        Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::Take {
+                taker: self.taker.pubkey(),
+                maker: self.maker.pubkey(),
+                mint_a: self.mint_a,
+                mint_b: self.mint_b,
+                taker_ata_a: Some(self.ata(&self.taker.pubkey(), &self.mint_a)),
+                taker_ata_b: Some(self.taker_ata_b),
+                maker_ata_b: Some(self.ata(&self.maker.pubkey(), &self.mint_b)),
+                config: self.config(),
+                treasury: self.treasury,
+                treasury_ata_b: Some(self.ata(&self.treasury, &self.mint_b)),
+                escrow: self.escrow,
+                order_book: self.order_book,
+                vault: Some(self.vault),
+                associated_token_program: associated_token::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::Take { amount }.data(),
+        }
+    }
+
+    // A fresh mint with `amount` of it in `owner`'s ATA.
+    fn fund_new_mint(&mut self, owner: &Pubkey, amount: u64) -> (Pubkey, Pubkey) {
+        let mint = CreateMint::new(&mut self.svm, &self.payer)
+            .decimals(DECIMALS)
+            .token_program_id(&self.token_program)
+            .send()
+            .unwrap();
+        let ata = CreateAssociatedTokenAccount::new(&mut self.svm, &self.payer, &mint)
+            .owner(owner)
+            .token_program_id(&self.token_program)
+            .send()
+            .unwrap();
+        if amount > 0 {
+            MintTo::new(&mut self.svm, &self.payer, &mint, &ata, amount)
+                .token_program_id(&self.token_program)
+                .send()
+                .unwrap();
+        }
+        (mint, ata)
+    }
+
+    fn refund(&mut self) {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts: escrow::accounts::Refund {
+                maker: self.maker.pubkey(),
+                mint_a: self.mint_a,
+                maker_ata_a: Some(self.maker_ata_a),
+                escrow: self.escrow,
+                order_book: self.order_book,
+                vault: Some(self.vault),
+                associated_token_program: associated_token::ID,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: escrow::instruction::Refund {}.data(),
+        };
+        let maker = self.maker.insecure_clone();
+        self.send(ix, &[&maker]);
+    }
+
+    fn balance(&self, token_account: &Pubkey) -> u64 {
+        let account = self.svm.get_account(token_account).expect("token account exists");
+        StateWithExtensions::<TokenAccount>::unpack(&account.data).unwrap().base.amount
+    }
+
+    fn lamports(&self, address: &Pubkey) -> u64 {
+        self.svm.get_account(address).map_or(0, |account| account.lamports)
+    }
+
+    fn assert_closed(&self, address: &Pubkey) {
+        if let Some(account) = self.svm.get_account(address) {
+            assert_eq!(account.lamports, 0, "{address} still holds lamports");
+            assert!(account.data.is_empty(), "{address} still holds data");
+        }
+    }
+
+    fn book(&self) -> OrderBook {
+        let account = self.svm.get_account(&self.order_book).unwrap();
+        anchor_lang::AccountDeserialize::try_deserialize(&mut &account.data[..]).unwrap()
+    }
+
+    fn offer(&self) -> Escrow {
+        let account = self.svm.get_account(&self.escrow).unwrap();
+        anchor_lang::AccountDeserialize::try_deserialize(&mut &account.data[..]).unwrap()
+    }
+}
+
+fn program_data() -> Pubkey {
+    Pubkey::find_program_address(&[escrow::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+// Lays the program out as `anchor deploy` would, behind the upgradeable
+// loader, since `initialize_config` checks the upgrade authority.
+fn deploy_upgradeable(svm: &mut LiteSVM, elf: &[u8], authority: &Pubkey) {
+    // `UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address }`
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend(0u64.to_le_bytes());
+    data.push(1);
+    data.extend(authority.as_ref());
+    data.extend(elf);
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: bpf_loader_upgradeable::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(program_data(), account).unwrap();
+
+    // `UpgradeableLoaderState::Program { programdata_address }`
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend(program_data().as_ref());
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: bpf_loader_upgradeable::ID,
+        executable: true,
+        rent_epoch: 0,
+    };
+    svm.set_account(escrow::ID, account).unwrap();
+}
+
+fn make_then_take(token_program: Pubkey) {
+    let mut s = Setup::new(token_program);
+    s.make();
+
+    assert_eq!(s.balance(&s.vault), DEPOSIT);
+    assert_eq!(s.balance(&s.maker_ata_a), MINTED - DEPOSIT);
+    let offer = s.offer();
+    assert_eq!(offer.maker, s.maker.pubkey());
+    assert_eq!(offer.remaining_deposit, DEPOSIT);
+    assert_eq!(offer.remaining_receive, RECEIVE);
+    assert_eq!(s.book().entries.len(), 1);
+
+    let rent = s.lamports(&s.escrow) + s.lamports(&s.vault);
+    let maker_lamports = s.lamports(&s.maker.pubkey());
+    s.take();
+
+    assert_eq!(s.balance(&s.ata(&s.taker.pubkey(), &s.mint_a)), DEPOSIT);
+    assert_eq!(s.balance(&s.taker_ata_b), MINTED - RECEIVE);
+    assert_eq!(s.balance(&s.ata(&s.maker.pubkey(), &s.mint_b)), RECEIVE);
+    assert_eq!(s.lamports(&s.maker.pubkey()), maker_lamports + rent);
+    s.assert_closed(&s.vault);
+    s.assert_closed(&s.escrow);
+    assert!(s.book().entries.is_empty());
+}
+
+fn make_then_refund(token_program: Pubkey) {
+    let mut s = Setup::new(token_program);
+    s.make();
+
+    let rent = s.lamports(&s.escrow) + s.lamports(&s.vault);
+    let maker_lamports = s.lamports(&s.maker.pubkey());
+    s.refund();
+
+    assert_eq!(s.balance(&s.maker_ata_a), MINTED);
+    assert_eq!(s.lamports(&s.maker.pubkey()), maker_lamports + rent);
+    s.assert_closed(&s.vault);
+    s.assert_closed(&s.escrow);
+    assert!(s.book().entries.is_empty());
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn make_take_spl_token() {
+    make_then_take(spl_token::ID);
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn make_take_token_2022() {
+    make_then_take(spl_token_2022::ID);
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn make_refund_spl_token() {
+    make_then_refund(spl_token::ID);
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn make_refund_token_2022() {
+    make_then_refund(spl_token_2022::ID);
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn full_book_keeps_cheapest_offers() {
+    let mut s = Setup::new(spl_token::ID);
+    let full = MAX_BOOK_ENTRIES as u64;
+    for seed in 0..full {
+        s.make_offer(seed, RECEIVE + seed);
+    }
+    assert_eq!(s.book().entries.len(), full as usize);
+
+    // A cheaper offer evicts the most expensive one
+    s.make_offer(full, RECEIVE - 1);
+    let book = s.book();
+    assert_eq!(book.entries.len(), full as usize);
+    assert_eq!(book.entries[0].escrow, s.escrow_for(full));
+    assert!(book.entries.iter().all(|entry| entry.escrow != s.escrow_for(full - 1)));
+
+    // A pricier one still opens, it just isn't indexed
+    s.make_offer(full + 1, RECEIVE * 2);
+    assert!(s.svm.get_account(&s.escrow_for(full + 1)).is_some());
+    assert!(s.book().entries.iter().all(|entry| entry.escrow != s.escrow_for(full + 1)));
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn partial_fills_release_pro_rata() {
+    let mut s = Setup::new(spl_token::ID);
+    s.make();
+
+    // 40% of the ask releases 40% of the deposit and stays on the book
+    s.try_take(200).unwrap();
+    let taker_ata_a = s.ata(&s.taker.pubkey(), &s.mint_a);
+    assert_eq!(s.balance(&taker_ata_a), 400);
+    assert_eq!(s.balance(&s.vault), 600);
+    let offer = s.offer();
+    assert_eq!(offer.remaining_deposit, 600);
+    assert_eq!(offer.remaining_receive, 300);
+    assert_eq!(s.book().entries[0].remaining_receive, 300);
+
+    // The rest closes the offer
+    s.try_take(300).unwrap();
+    assert_eq!(s.balance(&taker_ata_a), DEPOSIT);
+    s.assert_closed(&s.escrow);
+    assert!(s.book().entries.is_empty());
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn fill_below_minimum_fails() {
+    let mut s = Setup::new(spl_token::ID);
+    s.make();
+
+    assert_eq!(s.try_take(MIN_FILL - 1), Err(Some(EscrowError::FillTooSmall.into())));
+    assert_eq!(s.offer().remaining_receive, RECEIVE);
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn take_after_expiry_fails() {
+    let mut s = Setup::new(spl_token::ID);
+    s.make();
+
+    let mut clock = s.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += 3_601;
+    s.svm.set_sysvar(&clock);
+
+    assert_eq!(s.try_take(RECEIVE), Err(Some(EscrowError::OfferExpired.into())));
+    assert_eq!(s.balance(&s.vault), DEPOSIT);
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn config_needs_upgrade_authority() {
+    let mut s = Setup::deployed(spl_token::ID);
+
+    let maker = s.maker.insecure_clone();
+    assert_eq!(s.initialize_config(&maker), Err(Some(EscrowError::Unauthorized.into())));
+    let payer = s.payer.insecure_clone();
+    s.initialize_config(&payer).unwrap();
+}
+
+#[test]
+#[ignore = "needs the program built by `anchor build`"]
+fn failing_leg_reverts_basket_take() {
+    let mut s = Setup::new(spl_token::ID);
+    let maker = s.maker.pubkey();
+    let taker = s.taker.pubkey();
+
+    // The maker adds a second deposit and asks for a second token the taker
+    // holds too little of.
+    let (mint_c, maker_ata_c) = s.fund_new_mint(&maker, MINTED);
+    let (mint_d, taker_ata_d) = s.fund_new_mint(&taker, 299);
+    let taker_ata_c = CreateAssociatedTokenAccount::new(&mut s.svm, &s.payer, &mint_c)
+        .owner(&taker)
+        .token_program_id(&s.token_program)
+        .send()
+        .unwrap();
+    let vault_c = s.ata(&s.escrow, &mint_c);
+    let maker_ata_d = s.ata(&maker, &mint_d);
+
+    let mut ix = s.make_ix(
+        SEED,
+        RECEIVE,
+        vec![Leg { mint: mint_c, amount: 200 }],
+        vec![Leg { mint: mint_d, amount: 300 }],
+    );
+    ix.accounts.extend([
+        AccountMeta::new_readonly(mint_c, false),
+        AccountMeta::new(maker_ata_c, false),
+        AccountMeta::new(vault_c, false),
+    ]);
+    let maker_kp = s.maker.insecure_clone();
+    s.send(ix, &[&maker_kp]);
+    assert_eq!(s.balance(&vault_c), 200);
+
+    // The deposit leg would pay out fine, but the request leg can't be covered.
+    let mut ix = s.take_ix(RECEIVE);
+    ix.accounts.extend([
+        AccountMeta::new_readonly(mint_c, false),
+        AccountMeta::new(vault_c, false),
+        AccountMeta::new(taker_ata_c, false),
+        AccountMeta::new_readonly(mint_d, false),
+        AccountMeta::new(taker_ata_d, false),
+        AccountMeta::new(maker_ata_d, false),
+    ]);
+    let taker_kp = s.taker.insecure_clone();
+    assert!(s.try_send(ix, &[&taker_kp]).is_err());
+
+    // Nothing moved on any leg and the offer is still whole.
+    assert_eq!(s.balance(&s.vault), DEPOSIT);
+    assert_eq!(s.balance(&vault_c), 200);
+    assert_eq!(s.balance(&maker_ata_c), MINTED - 200);
+    assert_eq!(s.balance(&taker_ata_c), 0);
+    assert_eq!(s.balance(&s.taker_ata_b), MINTED);
+    assert_eq!(s.balance(&taker_ata_d), 299);
+    assert!(s.svm.get_account(&s.ata(&taker, &s.mint_a)).is_none());
+    assert!(s.svm.get_account(&s.ata(&maker, &s.mint_b)).is_none());
+    assert!(s.svm.get_account(&maker_ata_d).is_none());
+    assert_eq!(s.offer().remaining_receive, RECEIVE);
+    assert_eq!(s.book().entries.len(), 1);
+}
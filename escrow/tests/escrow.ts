@@ -1,9 +1,11 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, } from "@coral-xyz/anchor";
 import { Escrow } from "../target/types/escrow";
+import { TransferHook } from "../target/types/transfer_hook";
 import * as spl from "@solana/spl-token";
 import { Keypair, PublicKey } from "@solana/web3.js";
 import { assert } from "chai";
+import { deriveOrderBook, fetchOrderBookPage } from "./utils";
 
 describe("escrow", () => {
   const provider = anchor.AnchorProvider.env();
@@ -14,6 +16,8 @@ describe("escrow", () => {
   const SEED = new anchor.BN(1234);
   const DEPOSIT_AMOUNT = new anchor.BN(1000);
   const RECEIVE_AMOUNT = new anchor.BN(500);
+  const MIN_FILL = new anchor.BN(100);
+  const CRANK_REWARD = new anchor.BN(0);
   const DECIMALS = 9;
 
   // Test accounts
@@ -25,6 +29,16 @@ describe("escrow", () => {
   let makerAtaB: PublicKey;
   let takerAtaA: PublicKey;
   let takerAtaB: PublicKey;
+  let treasury: Keypair;
+  const [configPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  // Only the upgrade authority, the local wallet here, can create the config
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
 
   const anchorWallet = provider.wallet as anchor.Wallet;
 
@@ -62,8 +76,20 @@ describe("escrow", () => {
 
     await mintTo(mintA, makerAtaA, mintAmount);
     await mintTo(mintB, takerAtaB, mintAmount);
+
+    // Protocol fee starts at zero so the flows below see exact amounts
+    treasury = Keypair.generate();
+    await fundAccount(treasury.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+    await program.methods
+      .initializeConfig(0, treasury.publicKey)
+      .accountsPartial({ admin: anchorWallet.publicKey, programData, config: configPDA })
+      .rpc();
   });
 
+  function expiresIn(seconds: number): anchor.BN {
+    return new anchor.BN(Math.floor(Date.now() / 1000) + seconds);
+  }
+
   async function fundAccount(pubkey: anchor.web3.PublicKey, amount: number) {
     await provider.connection.confirmTransaction(
       await provider.connection.requestAirdrop(pubkey, amount),
@@ -110,7 +136,7 @@ describe("escrow", () => {
 
   it("should execute full escrow flow exchange between maker and taker", async () => {
     // Derive escrow PDA
-    const [escrowPDA, bump] = await anchor.web3.PublicKey.findProgramAddress(
+    const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
       [
         Buffer.from("escrow"),
         maker.publicKey.toBuffer(),
@@ -129,7 +155,7 @@ describe("escrow", () => {
 
     // Execute make instruction
     await program.methods
-      .make(SEED, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, bump)
+      .make(SEED, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, MIN_FILL, expiresIn(3600), CRANK_REWARD, null, [], [])
       .accountsPartial({
         maker: maker.publicKey,
         mintA,
@@ -159,18 +185,22 @@ describe("escrow", () => {
       .accountsStrict({
         taker: taker.publicKey,
         maker: maker.publicKey,
+        treasury: treasury.publicKey,
         mintA,
         mintB,
         takerAtaA: takerAtaA,
         takerAtaB: takerAtaB,
         makerAtaB: makerAtaB,
+        config: configPDA,
+        treasuryAtaB: await spl.getAssociatedTokenAddress(mintB, treasury.publicKey),
         escrow: escrowPDA,
+        orderBook: deriveOrderBook(mintA, mintB, program.programId)[0],
         vault: vault,
         associatedTokenProgram: spl.ASSOCIATED_TOKEN_PROGRAM_ID,
         tokenProgram: spl.TOKEN_PROGRAM_ID,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
-      .signers([taker, maker])
+      .signers([taker])
       .rpc();
 
     // Verify balances after take
@@ -194,7 +224,7 @@ describe("escrow", () => {
 
   it("should allow refund", async () => {
     // Derive escrow PDA
-    const [escrowPDA, bump] = await anchor.web3.PublicKey.findProgramAddress(
+    const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
       [
         Buffer.from("escrow"),
         maker.publicKey.toBuffer(),
@@ -212,13 +242,14 @@ describe("escrow", () => {
     );
 
     await program.methods
-      .make(SEED, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, bump)
+      .make(SEED, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, MIN_FILL, expiresIn(3600), CRANK_REWARD, null, [], [])
       .accountsStrict({
         maker: maker.publicKey,
         mintA,
         mintB,
         makerAtaA,
         escrow: escrowPDA,
+        orderBook: deriveOrderBook(mintA, mintB, program.programId)[0],
         vault: vault,
         associatedTokenProgram: spl.ASSOCIATED_TOKEN_PROGRAM_ID,
         tokenProgram: spl.TOKEN_PROGRAM_ID,
@@ -235,6 +266,7 @@ describe("escrow", () => {
         mintA,
         makerAtaA,
         escrow: escrowPDA,
+        orderBook: deriveOrderBook(mintA, mintB, program.programId)[0],
         vault: vault,
         associatedTokenProgram: spl.ASSOCIATED_TOKEN_PROGRAM_ID,
         tokenProgram: spl.TOKEN_PROGRAM_ID,
@@ -255,4 +287,1002 @@ describe("escrow", () => {
       assert.include(err.message, "Account does not exist");
     }
   });
-});
\ No newline at end of file
+
+  it("should fill an offer in parts across multiple takers", async () => {
+    const seed = new anchor.BN(5678);
+    const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("escrow"),
+        maker.publicKey.toBuffer(),
+        seed.toBuffer("le", 8)
+      ],
+      program.programId
+    );
+    const vault = await spl.getAssociatedTokenAddress(
+      mintA,
+      escrowPDA,
+      true,
+      spl.TOKEN_PROGRAM_ID
+    );
+
+    const secondTaker = Keypair.generate();
+    await fundAccount(secondTaker.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
+    const secondTakerAtaB = await createATA(mintB, secondTaker);
+    await mintTo(mintB, secondTakerAtaB, RECEIVE_AMOUNT);
+
+    await program.methods
+      .make(seed, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, MIN_FILL, expiresIn(3600), CRANK_REWARD, null, [], [])
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        makerAtaA,
+        escrow: escrowPDA,
+        vault,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    const takeFor = (who: Keypair, amount: anchor.BN) =>
+      program.methods
+        .take(amount)
+        .accountsPartial({
+          taker: who.publicKey,
+          maker: maker.publicKey,
+          treasury: treasury.publicKey,
+          mintA,
+          mintB,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([who])
+        .rpc();
+
+    // Fills below the maker's minimum are rejected
+    try {
+      await takeFor(taker, MIN_FILL.subn(1));
+      assert.fail("Fill below minimum should fail");
+    } catch (err) {
+      assert.include(err.message, "FillTooSmall");
+    }
+
+    // First taker fills 40% and receives 40% of the deposit
+    await takeFor(taker, new anchor.BN(200));
+    let escrowAccount = await program.account.escrow.fetch(escrowPDA);
+    assert.equal(escrowAccount.remainingReceive.toNumber(), 300);
+    assert.equal(escrowAccount.remainingDeposit.toNumber(), 600);
+
+    // Second taker fills the rest, closing the offer
+    await takeFor(secondTaker, new anchor.BN(300));
+    const secondTakerAtaA = await spl.getAssociatedTokenAddress(mintA, secondTaker.publicKey);
+    const secondTakerBalance = await spl.getAccount(provider.connection, secondTakerAtaA);
+    assert.equal(secondTakerBalance.amount.toString(), "600");
+
+    try {
+      await program.account.escrow.fetch(escrowPDA);
+      assert.fail("Escrow should be closed");
+    } catch (err) {
+      assert.include(err.message, "Account does not exist");
+    }
+  });
+
+  it("should let anyone expire a stale offer for the crank reward", async () => {
+    const seed = new anchor.BN(9012);
+    // The reward is capped at MAX_CRANK_REWARD_LAMPORTS.
+    const reward = new anchor.BN(10_000_000);
+    const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("escrow"),
+        maker.publicKey.toBuffer(),
+        seed.toBuffer("le", 8)
+      ],
+      program.programId
+    );
+    const vault = await spl.getAssociatedTokenAddress(
+      mintA,
+      escrowPDA,
+      true,
+      spl.TOKEN_PROGRAM_ID
+    );
+
+    const makeWithReward = (crankReward: anchor.BN) =>
+      program.methods
+        .make(seed, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, MIN_FILL, expiresIn(2), crankReward, null, [], [])
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA,
+          mintB,
+          makerAtaA,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+
+    try {
+      await makeWithReward(reward.addn(1));
+      assert.fail("A crank reward above the cap should fail");
+    } catch (err) {
+      assert.include(err.message, "InvalidCrankReward");
+    }
+    await makeWithReward(reward);
+
+    const cranker = Keypair.generate();
+    await fundAccount(cranker.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+    const expire = () =>
+      program.methods
+        .expire()
+        .accountsPartial({
+          cranker: cranker.publicKey,
+          maker: maker.publicKey,
+          mintA,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([cranker])
+        .rpc();
+
+    try {
+      await expire();
+      assert.fail("Expire before the deadline should fail");
+    } catch (err) {
+      assert.include(err.message, "OfferNotExpired");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    const makerBefore = await spl.getAccount(provider.connection, makerAtaA);
+    const crankerBefore = await provider.connection.getBalance(cranker.publicKey);
+    const signature = await expire();
+
+    const makerAfter = await spl.getAccount(provider.connection, makerAtaA);
+    assert.equal(
+      (makerAfter.amount - makerBefore.amount).toString(),
+      DEPOSIT_AMOUNT.toString()
+    );
+    const crankerAfter = await provider.connection.getBalance(cranker.publicKey);
+    const { meta } = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    assert.equal(crankerAfter - crankerBefore + meta.fee, reward.toNumber());
+
+    try {
+      await program.account.escrow.fetch(escrowPDA);
+      assert.fail("Escrow should be closed");
+    } catch (err) {
+      assert.include(err.message, "Account does not exist");
+    }
+  });
+
+  it("should only let the designated taker fill a private offer", async () => {
+    const seed = new anchor.BN(3456);
+    const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("escrow"),
+        maker.publicKey.toBuffer(),
+        seed.toBuffer("le", 8)
+      ],
+      program.programId
+    );
+    const vault = await spl.getAssociatedTokenAddress(
+      mintA,
+      escrowPDA,
+      true,
+      spl.TOKEN_PROGRAM_ID
+    );
+
+    const outsider = Keypair.generate();
+    await fundAccount(outsider.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+    const outsiderAtaB = await createATA(mintB, outsider);
+    await mintTo(mintB, outsiderAtaB, RECEIVE_AMOUNT);
+
+    await program.methods
+      .make(seed, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, MIN_FILL, expiresIn(3600), CRANK_REWARD, taker.publicKey, [], [])
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        makerAtaA,
+        escrow: escrowPDA,
+        vault,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    const escrowAccount = await program.account.escrow.fetch(escrowPDA);
+    assert.isTrue(escrowAccount.allowedTaker.equals(taker.publicKey));
+
+    const takeFor = (who: Keypair) =>
+      program.methods
+        .take(RECEIVE_AMOUNT)
+        .accountsPartial({
+          taker: who.publicKey,
+          maker: maker.publicKey,
+          treasury: treasury.publicKey,
+          mintA,
+          mintB,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([who])
+        .rpc();
+
+    try {
+      await takeFor(outsider);
+      assert.fail("Outsider should not be able to take a private offer");
+    } catch (err) {
+      assert.include(err.message, "TakerNotAllowed");
+    }
+
+    await takeFor(taker);
+    try {
+      await program.account.escrow.fetch(escrowPDA);
+      assert.fail("Escrow should be closed");
+    } catch (err) {
+      assert.include(err.message, "Account does not exist");
+    }
+  });
+
+  it("should swap a basket of mints on both sides atomically", async () => {
+    const seed = new anchor.BN(7890);
+    const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("escrow"),
+        maker.publicKey.toBuffer(),
+        seed.toBuffer("le", 8)
+      ],
+      program.programId
+    );
+    const vault = await spl.getAssociatedTokenAddress(mintA, escrowPDA, true);
+
+    // Extra deposit leg (mint C) and extra request leg (mint D)
+    const mintC = await createMint(DECIMALS);
+    const mintD = await createMint(DECIMALS);
+    const makerAtaC = await createATA(mintC, maker);
+    const takerAtaD = await createATA(mintD, taker);
+    await mintTo(mintC, makerAtaC, DEPOSIT_AMOUNT);
+    await mintTo(mintD, takerAtaD, RECEIVE_AMOUNT);
+
+    const vaultC = await spl.getAssociatedTokenAddress(mintC, escrowPDA, true);
+    const takerAtaC = await spl.getAssociatedTokenAddress(mintC, taker.publicKey);
+    const makerAtaD = await spl.getAssociatedTokenAddress(mintD, maker.publicKey);
+    const leg = (pubkey: PublicKey, isWritable: boolean) => ({ pubkey, isSigner: false, isWritable });
+
+    await program.methods
+      .make(
+        seed,
+        DEPOSIT_AMOUNT,
+        RECEIVE_AMOUNT,
+        RECEIVE_AMOUNT,
+        expiresIn(3600),
+        CRANK_REWARD,
+        null,
+        [{ mint: mintC, amount: DEPOSIT_AMOUNT }],
+        [{ mint: mintD, amount: RECEIVE_AMOUNT }]
+      )
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        makerAtaA,
+        escrow: escrowPDA,
+        vault,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([leg(mintC, false), leg(makerAtaC, true), leg(vaultC, true)])
+      .signers([maker])
+      .rpc();
+
+    const vaultCBalance = await spl.getAccount(provider.connection, vaultC);
+    assert.equal(vaultCBalance.amount.toString(), DEPOSIT_AMOUNT.toString());
+
+    await spl.createAssociatedTokenAccount(provider.connection, taker, mintC, taker.publicKey);
+    await program.methods
+      .take(RECEIVE_AMOUNT)
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        treasury: treasury.publicKey,
+        mintA,
+        mintB,
+        escrow: escrowPDA,
+        vault,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        leg(mintC, false), leg(vaultC, true), leg(takerAtaC, true),
+        leg(mintD, false), leg(takerAtaD, true), leg(makerAtaD, true),
+      ])
+      .signers([taker])
+      .rpc();
+
+    const takerCBalance = await spl.getAccount(provider.connection, takerAtaC);
+    assert.equal(takerCBalance.amount.toString(), DEPOSIT_AMOUNT.toString());
+    const makerDBalance = await spl.getAccount(provider.connection, makerAtaD);
+    assert.equal(makerDBalance.amount.toString(), RECEIVE_AMOUNT.toString());
+
+    try {
+      await spl.getAccount(provider.connection, vaultC);
+      assert.fail("Basket vault should be closed");
+    } catch (err) {
+      assert.instanceOf(err, spl.TokenAccountNotFoundError);
+    }
+  });
+
+  it("should trade native SOL for tokens without wrapping", async () => {
+    const seed = new anchor.BN(2468);
+    const lamports = new anchor.BN(anchor.web3.LAMPORTS_PER_SOL);
+    const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("escrow"),
+        maker.publicKey.toBuffer(),
+        seed.toBuffer("le", 8)
+      ],
+      program.programId
+    );
+
+    // SOL leg: no maker ATA and no vault, the escrow PDA holds the lamports
+    await program.methods
+      .make(seed, lamports, RECEIVE_AMOUNT, RECEIVE_AMOUNT, expiresIn(3600), CRANK_REWARD, null, [], [])
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA: spl.NATIVE_MINT,
+        mintB,
+        makerAtaA: null,
+        escrow: escrowPDA,
+        vault: null,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    const takerBefore = await provider.connection.getBalance(taker.publicKey);
+    await program.methods
+      .take(RECEIVE_AMOUNT)
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        treasury: treasury.publicKey,
+        mintA: spl.NATIVE_MINT,
+        mintB,
+        takerAtaA: null,
+        takerAtaB,
+        makerAtaB,
+        escrow: escrowPDA,
+        vault: null,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([taker])
+      .rpc();
+
+    const takerAfter = await provider.connection.getBalance(taker.publicKey);
+    assert.isAbove(takerAfter - takerBefore, lamports.toNumber() - 10_000);
+
+    try {
+      await program.account.escrow.fetch(escrowPDA);
+      assert.fail("Escrow should be closed");
+    } catch (err) {
+      assert.include(err.message, "Account does not exist");
+    }
+  });
+
+  it("should let the maker reprice and resize an open offer", async () => {
+    const seed = new anchor.BN(1357);
+    const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("escrow"),
+        maker.publicKey.toBuffer(),
+        seed.toBuffer("le", 8)
+      ],
+      program.programId
+    );
+    const vault = await spl.getAssociatedTokenAddress(mintA, escrowPDA, true);
+
+    await program.methods
+      .make(seed, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, MIN_FILL, expiresIn(3600), CRANK_REWARD, null, [], [])
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        makerAtaA,
+        escrow: escrowPDA,
+        vault,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    const amend = (receive: anchor.BN | null, topUp: number, withdraw: number) =>
+      program.methods
+        .amend(receive, null, new anchor.BN(topUp), new anchor.BN(withdraw))
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA,
+          newMintB: null,
+          makerAtaA,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+
+    // Ask for more and add to the deposit
+    await amend(new anchor.BN(800), 500, 0);
+    let escrowAccount = await program.account.escrow.fetch(escrowPDA);
+    assert.equal(escrowAccount.remainingReceive.toNumber(), 800);
+    assert.equal(escrowAccount.remainingDeposit.toNumber(), 1500);
+
+    // Pull part of the deposit back out
+    await amend(null, 0, 700);
+    escrowAccount = await program.account.escrow.fetch(escrowPDA);
+    assert.equal(escrowAccount.remainingDeposit.toNumber(), 800);
+    const vaultBalance = await spl.getAccount(provider.connection, vault);
+    assert.equal(vaultBalance.amount.toString(), "800");
+
+    // Withdrawing everything must go through refund instead
+    try {
+      await amend(null, 0, 800);
+      assert.fail("Amend should not empty the offer");
+    } catch (err) {
+      assert.include(err.message, "InvalidAmount");
+    }
+
+    // Switching the requested mint needs a minimum fill in the new mint's units
+    const newMintB = await createMint(DECIMALS);
+    const [newOrderBook] = PublicKey.findProgramAddressSync(
+      [Buffer.from("book"), mintA.toBuffer(), newMintB.toBuffer()],
+      program.programId
+    );
+    const switchMint = (minFill: anchor.BN | null) =>
+      program.methods
+        .amend(new anchor.BN(400), minFill, new anchor.BN(0), new anchor.BN(0))
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA,
+          newMintB,
+          newOrderBook,
+          makerAtaA,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+
+    try {
+      await switchMint(null);
+      assert.fail("Changing the mint without a minimum fill should fail");
+    } catch (err) {
+      assert.include(err.message, "InvalidMinFill");
+    }
+    await switchMint(new anchor.BN(40));
+    escrowAccount = await program.account.escrow.fetch(escrowPDA);
+    assert.isTrue(escrowAccount.mintB.equals(newMintB));
+    assert.equal(escrowAccount.minFill.toNumber(), 40);
+  });
+
+  describe("token-2022 extensions", () => {
+    async function createMint2022(
+      extensions: spl.ExtensionType[],
+      initExtensions: (mint: PublicKey) => anchor.web3.TransactionInstruction[]
+    ): Promise<PublicKey> {
+      const mint = Keypair.generate();
+      const space = spl.getMintLen(extensions);
+      const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+      const tx = new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.createAccount({
+          fromPubkey: anchorWallet.publicKey,
+          newAccountPubkey: mint.publicKey,
+          space,
+          lamports,
+          programId: spl.TOKEN_2022_PROGRAM_ID,
+        }),
+        ...initExtensions(mint.publicKey),
+        spl.createInitializeMintInstruction(
+          mint.publicKey,
+          DECIMALS,
+          anchorWallet.publicKey,
+          null,
+          spl.TOKEN_2022_PROGRAM_ID
+        )
+      );
+      await provider.sendAndConfirm(tx, [mint]);
+      return mint.publicKey;
+    }
+
+    async function make2022(
+      seed: anchor.BN,
+      mintA2022: PublicKey,
+      mintB2022: PublicKey,
+      hooks: anchor.web3.AccountMeta[] = []
+    ) {
+      const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("escrow"),
+          maker.publicKey.toBuffer(),
+          seed.toBuffer("le", 8)
+        ],
+        program.programId
+      );
+      const makerAta = spl.getAssociatedTokenAddressSync(
+        mintA2022,
+        maker.publicKey,
+        false,
+        spl.TOKEN_2022_PROGRAM_ID
+      );
+      const vault = spl.getAssociatedTokenAddressSync(
+        mintA2022,
+        escrowPDA,
+        true,
+        spl.TOKEN_2022_PROGRAM_ID
+      );
+
+      await program.methods
+        .make(seed, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, MIN_FILL, expiresIn(3600), CRANK_REWARD, null, [], [])
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA: mintA2022,
+          mintB: mintB2022,
+          makerAtaA: makerAta,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(hooks)
+        .signers([maker])
+        .rpc();
+      return { escrowPDA, vault, makerAta };
+    }
+
+    async function take2022(
+      escrowPDA: PublicKey,
+      vault: PublicKey,
+      mintA2022: PublicKey,
+      mintB2022: PublicKey,
+      hooks: anchor.web3.AccountMeta[] = []
+    ) {
+      await program.methods
+        .take(RECEIVE_AMOUNT)
+        .accountsPartial({
+          taker: taker.publicKey,
+          maker: maker.publicKey,
+          treasury: treasury.publicKey,
+          mintA: mintA2022,
+          mintB: mintB2022,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(hooks)
+        .signers([taker])
+        .rpc();
+    }
+
+    async function refund2022(
+      escrowPDA: PublicKey,
+      vault: PublicKey,
+      mintA2022: PublicKey,
+      mintB2022: PublicKey,
+      hooks: anchor.web3.AccountMeta[] = []
+    ) {
+      await program.methods
+        .refund()
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA: mintA2022,
+          makerAtaA: spl.getAssociatedTokenAddressSync(
+            mintA2022,
+            maker.publicKey,
+            false,
+            spl.TOKEN_2022_PROGRAM_ID
+          ),
+          escrow: escrowPDA,
+          vault,
+          orderBook: deriveOrderBook(mintA2022, mintB2022, program.programId)[0],
+          tokenProgram: spl.TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(hooks)
+        .signers([maker])
+        .rpc();
+    }
+
+    async function fundMaker2022(mint: PublicKey, owner: Keypair = maker) {
+      const ata = await spl.getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        owner,
+        mint,
+        owner.publicKey,
+        false,
+        undefined,
+        undefined,
+        spl.TOKEN_2022_PROGRAM_ID
+      );
+      await spl.mintTo(
+        provider.connection,
+        anchorWallet.payer,
+        mint,
+        ata.address,
+        anchorWallet.publicKey,
+        DEPOSIT_AMOUNT.toNumber(),
+        [],
+        undefined,
+        spl.TOKEN_2022_PROGRAM_ID
+      );
+    }
+
+    async function tokenBalance2022(owner: PublicKey, mint: PublicKey) {
+      const account = await spl.getAccount(
+        provider.connection,
+        spl.getAssociatedTokenAddressSync(mint, owner, false, spl.TOKEN_2022_PROGRAM_ID),
+        undefined,
+        spl.TOKEN_2022_PROGRAM_ID
+      );
+      return Number(account.amount);
+    }
+
+    // 1% fee, capped well above the deposit
+    const createFeeMint = () =>
+      createMint2022([spl.ExtensionType.TransferFeeConfig], (mint) => [
+        spl.createInitializeTransferFeeConfigInstruction(
+          mint,
+          anchorWallet.publicKey,
+          anchorWallet.publicKey,
+          100,
+          BigInt(1_000_000),
+          spl.TOKEN_2022_PROGRAM_ID
+        ),
+      ]);
+
+    it("should only put the net amount of a transfer-fee mint on offer", async () => {
+      const feeMint = await createFeeMint();
+      const plainMint = await createMint2022([], () => []);
+      await fundMaker2022(feeMint);
+
+      const { escrowPDA, vault } = await make2022(new anchor.BN(4242), feeMint, plainMint);
+
+      const escrowAccount = await program.account.escrow.fetch(escrowPDA);
+      const vaultAccount = await spl.getAccount(
+        provider.connection,
+        vault,
+        undefined,
+        spl.TOKEN_2022_PROGRAM_ID
+      );
+      assert.equal(escrowAccount.remainingDeposit.toNumber(), DEPOSIT_AMOUNT.toNumber() - 10);
+      assert.equal(vaultAccount.amount.toString(), escrowAccount.remainingDeposit.toString());
+    });
+
+    it("should close a transfer-fee vault on take despite withheld fees", async () => {
+      const feeMint = await createFeeMint();
+      const plainMint = await createMint2022([], () => []);
+      await fundMaker2022(feeMint);
+      await fundMaker2022(plainMint, taker);
+
+      const { escrowPDA, vault } = await make2022(new anchor.BN(4444), feeMint, plainMint);
+      await take2022(escrowPDA, vault, feeMint, plainMint);
+
+      // The vault kept 10 in withheld fees from the deposit; the release to
+      // the taker withholds another 10 on their side.
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+      assert.isNull(await provider.connection.getAccountInfo(escrowPDA));
+      assert.equal(await tokenBalance2022(taker.publicKey, feeMint), DEPOSIT_AMOUNT.toNumber() - 20);
+      assert.equal(await tokenBalance2022(maker.publicKey, plainMint), RECEIVE_AMOUNT.toNumber());
+    });
+
+    it("should close a transfer-fee vault on refund despite withheld fees", async () => {
+      const feeMint = await createFeeMint();
+      const plainMint = await createMint2022([], () => []);
+      await fundMaker2022(feeMint);
+
+      const { escrowPDA, vault } = await make2022(new anchor.BN(4545), feeMint, plainMint);
+      await refund2022(escrowPDA, vault, feeMint, plainMint);
+
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+      assert.isNull(await provider.connection.getAccountInfo(escrowPDA));
+      assert.equal(await tokenBalance2022(maker.publicKey, feeMint), DEPOSIT_AMOUNT.toNumber() - 20);
+    });
+
+    it("should forward transfer-hook accounts on make, take and refund", async () => {
+      const hookProgram = anchor.workspace.TransferHook as Program<TransferHook>;
+      const hookMint = await createMint2022([spl.ExtensionType.TransferHook], (mint) => [
+        spl.createInitializeTransferHookInstruction(
+          mint,
+          anchorWallet.publicKey,
+          hookProgram.programId,
+          spl.TOKEN_2022_PROGRAM_ID
+        ),
+      ]);
+      const plainMint = await createMint2022([], () => []);
+      const [extraMetas] = PublicKey.findProgramAddressSync(
+        [Buffer.from("extra-account-metas"), hookMint.toBuffer()],
+        hookProgram.programId
+      );
+      const [counter] = PublicKey.findProgramAddressSync(
+        [Buffer.from("counter"), hookMint.toBuffer()],
+        hookProgram.programId
+      );
+      await hookProgram.methods
+        .initializeExtraAccountMetaList()
+        .accountsPartial({ payer: anchorWallet.publicKey, extraAccountMetaList: extraMetas, mint: hookMint, counter })
+        .rpc();
+
+      await fundMaker2022(hookMint);
+      await fundMaker2022(hookMint);
+      await fundMaker2022(plainMint, taker);
+      const hooks = [
+        { pubkey: hookProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: extraMetas, isSigner: false, isWritable: false },
+        { pubkey: counter, isSigner: false, isWritable: true },
+      ];
+
+      // Without the hook's accounts Token-2022 can't run the hook
+      let rejected = false;
+      try {
+        await make2022(new anchor.BN(4646), hookMint, plainMint);
+      } catch (err) {
+        rejected = true;
+      }
+      assert.isTrue(rejected, "Transfer without hook accounts should fail");
+
+      const taken = await make2022(new anchor.BN(4646), hookMint, plainMint, hooks);
+      await take2022(taken.escrowPDA, taken.vault, hookMint, plainMint, hooks);
+      assert.isNull(await provider.connection.getAccountInfo(taken.vault));
+
+      const refunded = await make2022(new anchor.BN(4747), hookMint, plainMint, hooks);
+      await refund2022(refunded.escrowPDA, refunded.vault, hookMint, plainMint, hooks);
+      assert.isNull(await provider.connection.getAccountInfo(refunded.vault));
+
+      // make, take, make, refund
+      const { transfers } = await hookProgram.account.counter.fetch(counter);
+      assert.equal(transfers.toNumber(), 4);
+    });
+
+    it("should refuse non-transferable mints", async () => {
+      const soulbound = await createMint2022([spl.ExtensionType.NonTransferable], (mint) => [
+        spl.createInitializeNonTransferableMintInstruction(mint, spl.TOKEN_2022_PROGRAM_ID),
+      ]);
+      const plainMint = await createMint2022([], () => []);
+      await fundMaker2022(soulbound);
+
+      try {
+        await make2022(new anchor.BN(4343), soulbound, plainMint);
+        assert.fail("Non-transferable mint should be rejected");
+      } catch (err) {
+        assert.include(err.message, "UnsupportedMintExtension");
+      }
+    });
+  });
+
+  it("should index open offers cheapest-first per pair", async () => {
+    const offers = [
+      { seed: new anchor.BN(6001), receive: new anchor.BN(900) },
+      { seed: new anchor.BN(6002), receive: new anchor.BN(300) },
+    ];
+    const escrows: PublicKey[] = [];
+
+    for (const { seed, receive } of offers) {
+      const [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("escrow"),
+          maker.publicKey.toBuffer(),
+          seed.toBuffer("le", 8)
+        ],
+        program.programId
+      );
+      escrows.push(escrowPDA);
+
+      await program.methods
+        .make(seed, DEPOSIT_AMOUNT, receive, MIN_FILL, expiresIn(3600), CRANK_REWARD, null, [], [])
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA,
+          mintB,
+          makerAtaA,
+          escrow: escrowPDA,
+          vault: await spl.getAssociatedTokenAddress(mintA, escrowPDA, true),
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+    }
+
+    const firstPage = await fetchOrderBookPage(program, mintA, mintB, 0, 1);
+    assert.isTrue(firstPage[0].escrow.equals(escrows[1]));
+    assert.equal(firstPage[0].remainingReceive.toNumber(), 300);
+
+    // Refunding the best offer drops it from the book
+    await program.methods
+      .refund()
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        makerAtaA,
+        escrow: escrows[1],
+        vault: await spl.getAssociatedTokenAddress(mintA, escrows[1], true),
+        orderBook: deriveOrderBook(mintA, mintB, program.programId)[0],
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    const entries = await fetchOrderBookPage(program, mintA, mintB, 0, 10);
+    assert.isFalse(entries.some((entry) => entry.escrow.equals(escrows[1])));
+    assert.isTrue(entries.some((entry) => entry.escrow.equals(escrows[0])));
+  });
+
+  describe("failed legs abort the whole instruction", () => {
+    it("should leave the offer untouched when the taker can't pay", async () => {
+      const seed = new anchor.BN(8001);
+      const [escrowPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("escrow"),
+          maker.publicKey.toBuffer(),
+          seed.toBuffer("le", 8)
+        ],
+        program.programId
+      );
+      const vault = await spl.getAssociatedTokenAddress(mintA, escrowPDA, true);
+
+      await program.methods
+        .make(seed, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, MIN_FILL, expiresIn(3600), CRANK_REWARD, null, [], [])
+        .accountsPartial({
+          maker: maker.publicKey,
+          mintA,
+          mintB,
+          makerAtaA,
+          escrow: escrowPDA,
+          vault,
+          tokenProgram: spl.TOKEN_PROGRAM_ID,
+        })
+        .signers([maker])
+        .rpc();
+
+      // A taker with no mint B: the payment leg fails after `fill` has
+      // already booked the trade, so everything must roll back
+      const broke = Keypair.generate();
+      await fundAccount(broke.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      await createATA(mintB, broke);
+
+      try {
+        await program.methods
+          .take(RECEIVE_AMOUNT)
+          .accountsPartial({
+            taker: broke.publicKey,
+            maker: maker.publicKey,
+            treasury: treasury.publicKey,
+            mintA,
+            mintB,
+            escrow: escrowPDA,
+            vault,
+            tokenProgram: spl.TOKEN_PROGRAM_ID,
+          })
+          .signers([broke])
+          .rpc();
+        assert.fail("Take without funds should fail");
+      } catch (err) {
+        assert.notInclude(err.message, "Take without funds should fail");
+      }
+
+      const escrowAccount = await program.account.escrow.fetch(escrowPDA);
+      assert.equal(escrowAccount.remainingReceive.toNumber(), RECEIVE_AMOUNT.toNumber());
+      assert.equal(escrowAccount.remainingDeposit.toNumber(), DEPOSIT_AMOUNT.toNumber());
+      const vaultBalance = await spl.getAccount(provider.connection, vault);
+      assert.equal(vaultBalance.amount.toString(), DEPOSIT_AMOUNT.toString());
+      const brokeAtaA = await spl.getAssociatedTokenAddress(mintA, broke.publicKey);
+      assert.isNull(await provider.connection.getAccountInfo(brokeAtaA));
+    });
+
+    it("should not create an escrow when the deposit leg fails", async () => {
+      const seed = new anchor.BN(8002);
+      const [escrowPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("escrow"),
+          maker.publicKey.toBuffer(),
+          seed.toBuffer("le", 8)
+        ],
+        program.programId
+      );
+      const vault = await spl.getAssociatedTokenAddress(mintA, escrowPDA, true);
+      const makerBefore = await spl.getAccount(provider.connection, makerAtaA);
+
+      try {
+        await program.methods
+          .make(seed, new anchor.BN(makerBefore.amount.toString()).addn(1), RECEIVE_AMOUNT, MIN_FILL, expiresIn(3600), CRANK_REWARD, null, [], [])
+          .accountsPartial({
+            maker: maker.publicKey,
+            mintA,
+            mintB,
+            makerAtaA,
+            escrow: escrowPDA,
+            vault,
+            tokenProgram: spl.TOKEN_PROGRAM_ID,
+          })
+          .signers([maker])
+          .rpc();
+        assert.fail("Overdrawn deposit should fail");
+      } catch (err) {
+        assert.notInclude(err.message, "Overdrawn deposit should fail");
+      }
+
+      assert.isNull(await provider.connection.getAccountInfo(escrowPDA));
+      assert.isNull(await provider.connection.getAccountInfo(vault));
+      const makerAfter = await spl.getAccount(provider.connection, makerAtaA);
+      assert.equal(makerAfter.amount.toString(), makerBefore.amount.toString());
+    });
+  });
+
+  it("should route the protocol fee on take to the treasury", async () => {
+    const seed = new anchor.BN(9901);
+    const [escrowPDA] = anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("escrow"),
+        maker.publicKey.toBuffer(),
+        seed.toBuffer("le", 8)
+      ],
+      program.programId
+    );
+    const vault = await spl.getAssociatedTokenAddress(mintA, escrowPDA, true);
+    const treasuryAtaB = await spl.getAssociatedTokenAddress(mintB, treasury.publicKey);
+
+    const setFee = (feeBps: number) =>
+      program.methods
+        .updateConfig(feeBps, treasury.publicKey)
+        .accountsPartial({ admin: anchorWallet.publicKey, config: configPDA })
+        .rpc();
+
+    try {
+      await setFee(5_000);
+      assert.fail("Fee above the cap should be rejected");
+    } catch (err) {
+      assert.include(err.message, "InvalidFeeAmount");
+    }
+
+    // 2% fee
+    await setFee(200);
+
+    await program.methods
+      .make(seed, DEPOSIT_AMOUNT, RECEIVE_AMOUNT, RECEIVE_AMOUNT, expiresIn(3600), CRANK_REWARD, null, [], [])
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        makerAtaA,
+        escrow: escrowPDA,
+        vault,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    const makerBefore = await spl.getAccount(provider.connection, makerAtaB);
+    await program.methods
+      .take(RECEIVE_AMOUNT)
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        treasury: treasury.publicKey,
+        mintA,
+        mintB,
+        escrow: escrowPDA,
+        vault,
+        treasuryAtaB,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([taker])
+      .rpc();
+
+    const treasuryBalance = await spl.getAccount(provider.connection, treasuryAtaB);
+    assert.equal(treasuryBalance.amount.toString(), "10");
+    const makerAfter = await spl.getAccount(provider.connection, makerAtaB);
+    assert.equal((makerAfter.amount - makerBefore.amount).toString(), "490");
+
+    await setFee(0);
+  });
+});
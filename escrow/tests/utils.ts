@@ -0,0 +1,31 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Escrow } from "../target/types/escrow";
+
+
+export function deriveOrderBook(
+  mintA: anchor.web3.PublicKey,
+  mintB: anchor.web3.PublicKey,
+  programId: anchor.web3.PublicKey
+): [anchor.web3.PublicKey, number] {
+  return anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("book"), mintA.toBuffer(), mintB.toBuffer()],
+    programId
+  );
+}
+
+// Entries come back cheapest-first; an empty page means the book is exhausted.
+export async function fetchOrderBookPage(
+  program: Program<Escrow>,
+  mintA: anchor.web3.PublicKey,
+  mintB: anchor.web3.PublicKey,
+  page: number,
+  pageSize: number
+) {
+  const [orderBook] = deriveOrderBook(mintA, mintB, program.programId);
+  const book = await program.account.orderBook.fetchNullable(orderBook);
+  if (!book) {
+    return [];
+  }
+  return book.entries.slice(page * pageSize, (page + 1) * pageSize);
+}
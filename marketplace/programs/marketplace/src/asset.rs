@@ -0,0 +1,254 @@
+//! CPIs into Metaplex Core and Bubblegum for listings that aren't legacy
+//! token-metadata NFTs. Neither program's SDK builds against this Solana
+//! version, so the handful of instructions used here are encoded by hand.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+        pubkey,
+    },
+};
+
+use solana_keccak_hasher::hashv;
+
+use crate::error::MarketplaceError;
+
+pub const MPL_CORE_ID: Pubkey = pubkey!("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d");
+pub const MPL_BUBBLEGUM_ID: Pubkey = pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+pub const SPL_ACCOUNT_COMPRESSION_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+pub const SPL_NOOP_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+// Core instruction, plugin and authority tags.
+const CORE_ADD_PLUGIN_V1: u8 = 2;
+const CORE_REMOVE_PLUGIN_V1: u8 = 4;
+const CORE_TRANSFER_V1: u8 = 14;
+const CORE_TRANSFER_DELEGATE: u8 = 3;
+const CORE_AUTHORITY_ADDRESS: u8 = 3;
+const CORE_KEY_ASSET_V1: u8 = 1;
+const CORE_UPDATE_AUTHORITY_COLLECTION: u8 = 2;
+
+const BUBBLEGUM_TRANSFER: [u8; 8] = [163, 52, 200, 231, 140, 3, 69, 186];
+
+// Where a compressed NFT's leaf sits in its tree, as Bubblegum's `transfer`
+// takes it. `root` has to be the tree's current root.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LeafArgs {
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+}
+
+// A compressed NFT's metadata, laid out as Bubblegum's `MetadataArgs` so it
+// hashes the way the leaf's `data_hash` and `creator_hash` were built. Enums
+// are given by their variant index.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    pub token_standard: Option<u8>,
+    pub collection: Option<CompressedCollection>,
+    pub uses: Option<CompressedUses>,
+    pub token_program_version: u8,
+    pub creators: Vec<CompressedCreator>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedCollection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedUses {
+    pub use_method: u8,
+    pub remaining: u64,
+    pub total: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+impl CompressedMetadata {
+    // Checks the metadata against the hashes in `leaf`. Bubblegum checks
+    // those against the tree when the leaf moves, so once this passes the
+    // collection and creators can be trusted.
+    pub fn verify(&self, leaf: &LeafArgs) -> Result<()> {
+        let mut data = Vec::new();
+        self.serialize(&mut data)?;
+        let data_hash = hashv(&[hashv(&[&data]).as_ref(), &self.seller_fee_basis_points.to_le_bytes()]);
+
+        let creators: Vec<_> = self.creators.iter()
+            .map(|creator| [creator.address.as_ref(), &[creator.verified as u8, creator.share]].concat())
+            .collect();
+        let creator_hash = hashv(&creators.iter().map(Vec::as_slice).collect::<Vec<_>>());
+
+        require!(
+            data_hash.to_bytes() == leaf.data_hash && creator_hash.to_bytes() == leaf.creator_hash,
+            MarketplaceError::InvalidCompressedMetadata
+        );
+        Ok(())
+    }
+
+    pub fn in_collection(&self, collection: &Pubkey) -> bool {
+        self.collection.as_ref().is_some_and(|c| c.verified && c.key == *collection)
+    }
+
+    // The verified creators' addresses and shares, in metadata order.
+    pub fn verified_creators(&self) -> Vec<(Pubkey, u8)> {
+        self.creators.iter()
+            .filter(|creator| creator.verified)
+            .map(|creator| (creator.address, creator.share))
+            .collect()
+    }
+}
+
+// Reads the owner and the collection, if any, off a Core asset.
+pub fn core_asset(asset: &AccountInfo) -> Result<(Pubkey, Option<Pubkey>)> {
+    require_keys_eq!(*asset.owner, MPL_CORE_ID, MarketplaceError::InvalidAsset);
+    let data = asset.try_borrow_data()?;
+    require!(data.len() >= 66 && data[0] == CORE_KEY_ASSET_V1, MarketplaceError::InvalidAsset);
+
+    let owner = Pubkey::try_from(&data[1..33]).unwrap();
+    let collection = (data[33] == CORE_UPDATE_AUTHORITY_COLLECTION).then(|| Pubkey::try_from(&data[34..66]).unwrap());
+    Ok((owner, collection))
+}
+
+// Gives `delegate` a transfer delegate plugin on the asset, signed by its owner.
+pub fn core_approve_transfer_delegate<'info>(
+    core_program: &AccountInfo<'info>,
+    asset: &AccountInfo<'info>,
+    collection: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    delegate: Pubkey,
+) -> Result<()> {
+    let mut data = vec![CORE_ADD_PLUGIN_V1, CORE_TRANSFER_DELEGATE, 1, CORE_AUTHORITY_ADDRESS];
+    data.extend_from_slice(delegate.as_ref());
+    core_invoke(core_program, asset, collection, owner, owner, None, system_program, data, &[])
+}
+
+// Drops the transfer delegate plugin again, signed by the asset's owner.
+pub fn core_revoke_transfer_delegate<'info>(
+    core_program: &AccountInfo<'info>,
+    asset: &AccountInfo<'info>,
+    collection: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let data = vec![CORE_REMOVE_PLUGIN_V1, CORE_TRANSFER_DELEGATE];
+    core_invoke(core_program, asset, collection, owner, owner, None, system_program, data, &[])
+}
+
+// Moves the asset to `new_owner` with `delegate` signing as its transfer
+// delegate. Core resets the delegate to the new owner afterwards.
+#[allow(clippy::too_many_arguments)]
+pub fn core_transfer<'info>(
+    core_program: &AccountInfo<'info>,
+    asset: &AccountInfo<'info>,
+    collection: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    delegate: &AccountInfo<'info>,
+    new_owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let data = vec![CORE_TRANSFER_V1, 0];
+    core_invoke(core_program, asset, collection, payer, delegate, Some(new_owner), system_program, data, signer_seeds)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn core_invoke<'info>(
+    core_program: &AccountInfo<'info>,
+    asset: &AccountInfo<'info>,
+    collection: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    new_owner: Option<&AccountInfo<'info>>,
+    system_program: &AccountInfo<'info>,
+    data: Vec<u8>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    // Core takes its own id in place of an optional account that's left out;
+    // the log wrapper always is.
+    let mut accounts = vec![
+        AccountMeta::new(asset.key(), false),
+        AccountMeta::new(collection.key(), false),
+        AccountMeta::new(payer.key(), true),
+        AccountMeta::new_readonly(authority.key(), true),
+    ];
+    let mut infos = vec![asset.clone(), collection.clone(), payer.clone(), authority.clone()];
+    if let Some(new_owner) = new_owner {
+        accounts.push(AccountMeta::new_readonly(new_owner.key(), false));
+        infos.push(new_owner.clone());
+    }
+    accounts.push(AccountMeta::new_readonly(system_program.key(), false));
+    accounts.push(AccountMeta::new_readonly(MPL_CORE_ID, false));
+    infos.extend([system_program.clone(), core_program.clone()]);
+
+    let ix = Instruction { program_id: MPL_CORE_ID, accounts, data };
+    invoke_signed(&ix, &infos, signer_seeds).map_err(Into::into)
+}
+
+pub fn compressed_asset_id(merkle_tree: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"asset", merkle_tree.as_ref(), &nonce.to_le_bytes()], &MPL_BUBBLEGUM_ID).0
+}
+
+// Transfers a compressed NFT from `leaf_owner`, who signs as both owner and
+// delegate. The leaf's proof path comes in as `proof`.
+#[allow(clippy::too_many_arguments)]
+pub fn bubblegum_transfer<'info>(
+    bubblegum_program: &AccountInfo<'info>,
+    tree_config: &AccountInfo<'info>,
+    leaf_owner: &AccountInfo<'info>,
+    new_leaf_owner: &AccountInfo<'info>,
+    merkle_tree: &AccountInfo<'info>,
+    log_wrapper: &AccountInfo<'info>,
+    compression_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    leaf: &LeafArgs,
+    proof: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(tree_config.key(), false),
+        AccountMeta::new_readonly(leaf_owner.key(), true),
+        AccountMeta::new_readonly(leaf_owner.key(), false),
+        AccountMeta::new_readonly(new_leaf_owner.key(), false),
+        AccountMeta::new(merkle_tree.key(), false),
+        AccountMeta::new_readonly(log_wrapper.key(), false),
+        AccountMeta::new_readonly(compression_program.key(), false),
+        AccountMeta::new_readonly(system_program.key(), false),
+    ];
+    accounts.extend(proof.iter().map(|node| AccountMeta::new_readonly(node.key(), false)));
+
+    let mut data = BUBBLEGUM_TRANSFER.to_vec();
+    leaf.serialize(&mut data)?;
+
+    let mut infos = vec![
+        bubblegum_program.clone(),
+        tree_config.clone(),
+        leaf_owner.clone(),
+        new_leaf_owner.clone(),
+        merkle_tree.clone(),
+        log_wrapper.clone(),
+        compression_program.clone(),
+        system_program.clone(),
+    ];
+    infos.extend_from_slice(proof);
+
+    let ix = Instruction { program_id: MPL_BUBBLEGUM_ID, accounts, data };
+    invoke_signed(&ix, &infos, signer_seeds).map_err(Into::into)
+}
@@ -0,0 +1,9 @@
+use anchor_lang::prelude::*;
+
+// Cap on the marketplace fee, in basis points.
+#[constant]
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+// Cap on the rewards rate, in reward base units per SOL: 1,000 reward tokens.
+#[constant]
+pub const MAX_REWARDS_RATE: u64 = 1_000_000_000;
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum MarketplaceError {
+    #[msg("marketplace name must be between 1 and 32 bytes")]
+    InvalidName,
+    #[msg("fee exceeds MAX_FEE_BPS")]
+    InvalidFee,
+    #[msg("price must be greater than zero")]
+    InvalidPrice,
+    #[msg("NFT is not a verified member of the collection")]
+    CollectionNotVerified,
+    #[msg("overflow on calculation")]
+    Overflow,
+    #[msg("treasury can't go below its rent-exempt minimum")]
+    InsufficientTreasury,
+    #[msg("remaining accounts must be the verified creators' wallets in metadata order")]
+    InvalidCreatorAccounts,
+    #[msg("payment accounts don't match the listing's payment mint")]
+    PaymentMintMismatch,
+    #[msg("bid is for a different mint")]
+    BidMintMismatch,
+    #[msg("listing isn't sold this way")]
+    NotForSale,
+    #[msg("auction duration must be positive and its floor below its start price")]
+    InvalidAuction,
+    #[msg("English auctions are priced in SOL")]
+    AuctionNotInSol,
+    #[msg("auction has ended")]
+    AuctionEnded,
+    #[msg("auction is still running")]
+    AuctionNotEnded,
+    #[msg("bid is below the reserve or the minimum increment")]
+    BidTooLow,
+    #[msg("bidder account doesn't match the auction's highest bidder")]
+    HighestBidderMismatch,
+    #[msg("auction already has bids")]
+    AuctionHasBids,
+    #[msg("account isn't a Metaplex Core asset")]
+    InvalidAsset,
+    #[msg("asset isn't owned by the maker")]
+    NotAssetOwner,
+    #[msg("NFT accounts don't match how the listing holds it")]
+    ListingAccountsMismatch,
+    #[msg("remaining accounts don't match the batch layout")]
+    InvalidBatchAccounts,
+    #[msg("no candidate listing fits the sweep's limits")]
+    NothingToSweep,
+    #[msg("rewards rate is above the maximum")]
+    InvalidRewardsRate,
+    #[msg("signer isn't the proposed admin")]
+    NotPendingAdmin,
+    #[msg("metadata doesn't hash to the leaf's data and creator hashes")]
+    InvalidCompressedMetadata,
+}
@@ -0,0 +1,266 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
+    token_interface::{close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::MarketplaceError, payment, rewards, state::{Bid, Marketplace}};
+
+#[derive(Accounts)]
+pub struct AcceptBid<'info> {
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(mut)]
+    pub bidder: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    #[account(mint::token_program = token_program)]
+    pub seller_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(mut,
+    associated_token::mint = seller_mint,
+    associated_token::authority = seller,
+    associated_token::token_program = token_program)]
+    pub seller_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = seller_mint,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program
+    )]
+    pub bidder_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        mut,
+        close = bidder,
+        has_one = bidder,
+        seeds = [b"bid", marketplace.key().as_ref(), bidder.key().as_ref(), bid.seed.to_le_bytes().as_ref()],
+        bump = bid.bump,
+        constraint = bid.mint.is_none() || bid.mint == Some(seller_mint.key()) @ MarketplaceError::BidMintMismatch,
+        constraint = bid.payment_mint == payment_mint.as_ref().map(|mint| mint.key()) @ MarketplaceError::PaymentMintMismatch,
+    )]
+    pub bid: Account<'info,Bid>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            seller_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+        constraint = metadata.collection.as_ref().is_some_and(|collection| collection.verified && collection.key == bid.collection) @ MarketplaceError::CollectionNotVerified,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            seller_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Account<'info,MasterEditionAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+        mint::token_program = token_program
+    )]
+    pub rewards_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program
+    )]
+    pub bidder_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = seller,
+        associated_token::token_program = token_program
+    )]
+    pub seller_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    // The accounts below are only passed for bids in a token.
+    #[account(mint::token_program = payment_token_program)]
+    pub payment_mint: Option<InterfaceAccount<'info,Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = bid,
+        associated_token::token_program = payment_token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = payment_mint,
+        associated_token::authority = seller,
+        associated_token::token_program = payment_token_program
+    )]
+    pub seller_payment_ata: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = payment_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = payment_token_program
+    )]
+    pub treasury_payment_ata: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    pub payment_token_program: Option<Interface<'info,TokenInterface>>,
+
+    pub metadata_program: Program<'info,Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> AcceptBid<'info> {
+    // Settles the bid the same way a purchase pays for a listing: the
+    // marketplace fee and verified creator royalties come out of the price
+    // and the seller gets the rest. Royalty recipients come in as remaining
+    // accounts, in metadata order.
+    pub fn pay(&mut self, creators: &[AccountInfo<'info>]) -> Result<()> {
+        let price = self.bid.price;
+        let fee = self.marketplace.fee_on(price)?;
+        let payment_mint = match (&self.payment_mint, &self.payment_token_program) {
+            (Some(mint), Some(token_program)) => Some((mint.key(), token_program.key())),
+            _ => None,
+        };
+        let royalties = payment::royalties(&self.metadata, price, payment_mint, creators)?;
+        let royalty_total = royalties.iter().map(|(_, amount)| amount).sum::<u64>();
+        let deductions = fee.checked_add(royalty_total).ok_or(MarketplaceError::Overflow)?;
+        let proceeds = price.checked_sub(deductions).ok_or(MarketplaceError::Overflow)?;
+
+        let (seller, treasury) = match (&self.seller_payment_ata, &self.treasury_payment_ata) {
+            (Some(seller), Some(treasury)) => (seller.to_account_info(), treasury.to_account_info()),
+            _ => (self.seller.to_account_info(), self.treasury.to_account_info()),
+        };
+        for (to, amount) in royalties.into_iter().chain([(seller, proceeds), (treasury, fee)]) {
+            self.transfer_payment(to, amount)?;
+        }
+        Ok(())
+    }
+
+    // Escrowed lamports live in the bid account itself, which the program
+    // owns, so they are moved directly rather than through the system program.
+    fn transfer_payment(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if self.bid.payment_mint.is_none() {
+            self.bid.sub_lamports(amount)?;
+            to.add_lamports(amount)?;
+            return Ok(());
+        }
+
+        match (&self.payment_mint, &self.vault, &self.seller_payment_ata, &self.treasury_payment_ata, &self.payment_token_program) {
+            (Some(mint), Some(vault), Some(_), Some(_), Some(token_program)) => {
+                let marketplace = self.marketplace.key();
+                let bidder = self.bidder.key();
+                let seed = self.bid.seed.to_le_bytes();
+                let signer_seeds: [&[&[u8]] ;1] = [&[
+                    b"bid",
+                    marketplace.as_ref(),
+                    bidder.as_ref(),
+                    seed.as_ref(),
+                    &[self.bid.bump]
+                ]];
+
+                payment::transfer_tokens(
+                    &token_program.to_account_info(),
+                    &vault.to_account_info(),
+                    &mint.to_account_info(),
+                    &to,
+                    &self.bid.to_account_info(),
+                    amount,
+                    mint.decimals,
+                    &signer_seeds,
+                )
+            }
+            _ => err!(MarketplaceError::PaymentMintMismatch),
+        }
+    }
+
+    // Both sides earn rewards on a SOL bid's price, like a purchase.
+    pub fn mint_rewards(&mut self) -> Result<()> {
+        if self.payment_mint.is_some() {
+            return Ok(());
+        }
+        rewards::mint_rewards(
+            &self.marketplace,
+            &self.rewards_mint.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.bidder.key(),
+            &self.seller.key(),
+            [self.bidder_rewards_ata.to_account_info(), self.seller_rewards_ata.to_account_info()],
+            self.bid.price,
+        )
+    }
+
+    pub fn transfer_nft(&mut self) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.seller_ata.to_account_info(),
+            mint: self.seller_mint.to_account_info(),
+            to: self.bidder_ata.to_account_info(),
+            authority: self.seller.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, 1, self.seller_mint.decimals)
+    }
+
+    // The token vault's rent goes back to the bidder along with the bid's.
+    pub fn close_vault(&mut self) -> Result<()> {
+        let (Some(vault), Some(token_program)) = (&self.vault, &self.payment_token_program) else {
+            return Ok(());
+        };
+
+        let marketplace = self.marketplace.key();
+        let bidder = self.bidder.key();
+        let seed = self.bid.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            b"bid",
+            marketplace.as_ref(),
+            bidder.as_ref(),
+            seed.as_ref(),
+            &[self.bid.bump]
+        ]];
+
+        let close_accounts = CloseAccount {
+            account: vault.to_account_info(),
+            destination: self.bidder.to_account_info(),
+            authority: self.bid.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), close_accounts, &signer_seeds);
+        close_account(cpi_ctx)
+    }
+}
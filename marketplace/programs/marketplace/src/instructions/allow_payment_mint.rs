@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::state::{AcceptedMint, Marketplace};
+
+#[derive(Accounts)]
+pub struct AllowPaymentMint<'info> {
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    pub payment_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"accepted", marketplace.key().as_ref(), payment_mint.key().as_ref()],
+        bump,
+        space = 8 + AcceptedMint::INIT_SPACE,
+    )]
+    pub accepted_mint: Account<'info,AcceptedMint>,
+
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> AllowPaymentMint<'info> {
+    pub fn allow_payment_mint(&mut self, bumps: &AllowPaymentMintBumps) -> Result<()> {
+        self.accepted_mint.set_inner(AcceptedMint {
+            mint: self.payment_mint.key(),
+            bump: bumps.accepted_mint,
+        });
+        Ok(())
+    }
+}
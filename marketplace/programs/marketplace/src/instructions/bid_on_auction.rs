@@ -0,0 +1,73 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+use crate::{error::MarketplaceError, state::{Listing, Marketplace, SaleKind}};
+
+#[derive(Accounts)]
+pub struct BidOnAuction<'info> {
+
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    #[account(
+        mut,
+        seeds = [marketplace.key().as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    // The bidder being outbid, if any; their bid is refunded.
+    #[account(mut)]
+    pub previous_bidder: Option<SystemAccount<'info>>,
+
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> BidOnAuction<'info> {
+    pub fn bid(&mut self, amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let SaleKind::English { end_ts, min_increment, extension, highest_bidder, highest_bid } = self.listing.kind else {
+            return err!(MarketplaceError::NotForSale);
+        };
+        require!(now < end_ts, MarketplaceError::AuctionEnded);
+
+        let minimum = match highest_bidder {
+            Some(_) => highest_bid.checked_add(min_increment).ok_or(MarketplaceError::Overflow)?,
+            None => self.listing.price,
+        };
+        require!(amount >= minimum, MarketplaceError::BidTooLow);
+
+        let cpi_accounts = Transfer {
+            from: self.bidder.to_account_info(),
+            to: self.listing.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+        transfer(cpi_ctx, amount)?;
+
+        // The listing account owns the escrowed bid, so the refund moves
+        // lamports directly.
+        if let Some(previous) = highest_bidder {
+            let Some(previous_bidder) = &self.previous_bidder else {
+                return err!(MarketplaceError::HighestBidderMismatch);
+            };
+            require_keys_eq!(previous, previous_bidder.key(), MarketplaceError::HighestBidderMismatch);
+
+            self.listing.sub_lamports(highest_bid)?;
+            previous_bidder.add_lamports(highest_bid)?;
+        }
+
+        self.listing.kind = SaleKind::English {
+            end_ts: end_ts.max(now + extension),
+            min_increment,
+            extension,
+            highest_bidder: Some(self.bidder.key()),
+            highest_bid: amount,
+        };
+        Ok(())
+    }
+}
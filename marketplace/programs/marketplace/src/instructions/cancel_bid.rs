@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface};
+
+use crate::{error::MarketplaceError, payment, state::{Bid, Marketplace}};
+
+#[derive(Accounts)]
+pub struct CancelBid<'info> {
+
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    // Escrowed lamports come back with the account's rent on close.
+    #[account(
+        mut,
+        close = bidder,
+        has_one = bidder,
+        seeds = [b"bid", marketplace.key().as_ref(), bidder.key().as_ref(), bid.seed.to_le_bytes().as_ref()],
+        bump = bid.bump,
+        constraint = bid.payment_mint == payment_mint.as_ref().map(|mint| mint.key()) @ MarketplaceError::PaymentMintMismatch,
+    )]
+    pub bid: Account<'info,Bid>,
+
+    // The accounts below are only passed for bids in a token.
+    #[account(mint::token_program = payment_token_program)]
+    pub payment_mint: Option<InterfaceAccount<'info,Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = bidder,
+        associated_token::token_program = payment_token_program
+    )]
+    pub bidder_payment_ata: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = bid,
+        associated_token::token_program = payment_token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    pub payment_token_program: Option<Interface<'info,TokenInterface>>,
+}
+
+impl<'info> CancelBid<'info> {
+    pub fn refund_tokens(&mut self) -> Result<()> {
+        if self.bid.payment_mint.is_none() {
+            return Ok(());
+        }
+        let (Some(mint), Some(to), Some(vault), Some(token_program)) = (&self.payment_mint, &self.bidder_payment_ata, &self.vault, &self.payment_token_program) else {
+            return err!(MarketplaceError::PaymentMintMismatch);
+        };
+
+        let marketplace = self.marketplace.key();
+        let bidder = self.bidder.key();
+        let seed = self.bid.seed.to_le_bytes();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            b"bid",
+            marketplace.as_ref(),
+            bidder.as_ref(),
+            seed.as_ref(),
+            &[self.bid.bump]
+        ]];
+
+        payment::transfer_tokens(
+            &token_program.to_account_info(),
+            &vault.to_account_info(),
+            &mint.to_account_info(),
+            &to.to_account_info(),
+            &self.bid.to_account_info(),
+            vault.amount,
+            mint.decimals,
+            &signer_seeds,
+        )?;
+
+        let close_accounts = CloseAccount {
+            account: vault.to_account_info(),
+            destination: self.bidder.to_account_info(),
+            authority: self.bid.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), close_accounts, &signer_seeds);
+        close_account(cpi_ctx)
+    }
+}
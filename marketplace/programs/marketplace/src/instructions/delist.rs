@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{mpl_token_metadata, thaw_delegated_account, MasterEditionAccount, Metadata, ThawDelegatedAccount},
+    token_interface::{close_account, revoke, transfer_checked, CloseAccount, Mint, Revoke, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::MarketplaceError, state::{AssetStandard, Listing, Marketplace}};
+
+#[derive(Accounts)]
+pub struct Delist<'info> {
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    #[account(mint::token_program = token_program)]
+    pub maker_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = maker_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [marketplace.key().as_ref(), maker_mint.key().as_ref()],
+        bump = listing.bump,
+        constraint = !listing.has_bids() @ MarketplaceError::AuctionHasBids,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    // Holds the NFT for an escrowed listing.
+    #[account(
+        mut,
+        associated_token::mint = maker_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    // What a delegated listing needs to thaw the maker's ATA.
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            maker_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump,
+    )]
+    pub master_edition: Option<Account<'info,MasterEditionAccount>>,
+
+    pub metadata_program: Option<Program<'info,Metadata>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> Delist<'info> {
+    // Hands the NFT back: out of the vault for an escrowed listing, or by
+    // thawing the maker's ATA and dropping the delegate for a delegated one.
+    pub fn withdraw_nft(&mut self) -> Result<()> {
+        let marketplace = self.marketplace.key();
+        let mint = self.maker_mint.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        match (self.listing.standard, &self.vault, &self.master_edition, &self.metadata_program) {
+            (AssetStandard::Legacy, Some(vault), _, _) => {
+                let cpi_accounts = TransferChecked {
+                    from: vault.to_account_info(),
+                    mint: self.maker_mint.to_account_info(),
+                    to: self.maker_ata.to_account_info(),
+                    authority: self.listing.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+                transfer_checked(cpi_ctx, 1, self.maker_mint.decimals)
+            }
+            (AssetStandard::LegacyDelegated, _, Some(master_edition), Some(metadata_program)) => {
+                let cpi_accounts = ThawDelegatedAccount {
+                    metadata: metadata_program.to_account_info(),
+                    delegate: self.listing.to_account_info(),
+                    token_account: self.maker_ata.to_account_info(),
+                    edition: master_edition.to_account_info(),
+                    mint: self.maker_mint.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(metadata_program.to_account_info(), cpi_accounts, &signer_seeds);
+                thaw_delegated_account(cpi_ctx)?;
+
+                let cpi_accounts = Revoke {
+                    source: self.maker_ata.to_account_info(),
+                    authority: self.maker.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+                revoke(cpi_ctx)
+            }
+            _ => err!(MarketplaceError::ListingAccountsMismatch),
+        }
+    }
+
+    pub fn close_vault(&mut self) -> Result<()> {
+        let Some(vault) = &self.vault else {
+            return Ok(());
+        };
+
+        let marketplace = self.marketplace.key();
+        let mint = self.maker_mint.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        let close_accounts = CloseAccount {
+            account: vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.listing.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), close_accounts, &signer_seeds);
+        close_account(cpi_ctx)
+    }
+}
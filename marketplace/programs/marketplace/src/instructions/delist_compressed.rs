@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::{asset::{self, LeafArgs}, error::MarketplaceError, state::{AssetStandard, Listing, Marketplace}};
+
+#[derive(Accounts)]
+#[instruction(leaf: LeafArgs)]
+pub struct DelistCompressed<'info> {
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    /// CHECK: Bubblegum verifies the leaf against this tree.
+    #[account(mut, owner = asset::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Bubblegum's config PDA for the tree.
+    #[account(seeds = [merkle_tree.key().as_ref()], bump, seeds::program = asset::MPL_BUBBLEGUM_ID)]
+    pub tree_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [marketplace.key().as_ref(), asset::compressed_asset_id(&merkle_tree.key(), leaf.nonce).as_ref()],
+        bump = listing.bump,
+        constraint = listing.standard == AssetStandard::Compressed @ MarketplaceError::NotForSale,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    /// CHECK: the Bubblegum program.
+    #[account(address = asset::MPL_BUBBLEGUM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: the SPL noop program.
+    #[account(address = asset::SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: the SPL account compression program.
+    #[account(address = asset::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> DelistCompressed<'info> {
+    pub fn withdraw_nft(&mut self, leaf: &LeafArgs, proof: &[AccountInfo<'info>]) -> Result<()> {
+        let marketplace = self.marketplace.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            self.listing.mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        asset::bubblegum_transfer(
+            &self.bubblegum_program,
+            &self.tree_config,
+            &self.listing.to_account_info(),
+            &self.maker,
+            &self.merkle_tree,
+            &self.log_wrapper,
+            &self.compression_program,
+            &self.system_program,
+            leaf,
+            proof,
+            &signer_seeds,
+        )
+    }
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{asset, error::MarketplaceError, state::{AssetStandard, Listing, Marketplace}};
+
+#[derive(Accounts)]
+pub struct DelistCore<'info> {
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    /// CHECK: the listed asset, pinned by the listing's seeds.
+    #[account(mut, owner = asset::MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+
+    /// CHECK: the asset's collection, or the Core program id if it has none;
+    /// Core checks it.
+    #[account(mut)]
+    pub collection: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [marketplace.key().as_ref(), asset.key().as_ref()],
+        bump = listing.bump,
+        constraint = listing.standard == AssetStandard::Core @ MarketplaceError::NotForSale,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    /// CHECK: the Core program.
+    #[account(address = asset::MPL_CORE_ID)]
+    pub core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> DelistCore<'info> {
+    pub fn revoke_delegate(&mut self) -> Result<()> {
+        asset::core_revoke_transfer_delegate(
+            &self.core_program,
+            &self.asset,
+            &self.collection,
+            &self.maker,
+            &self.system_program,
+        )
+    }
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{AcceptedMint, Marketplace};
+
+// Stops new listings in the mint; listings already priced in it can still
+// be bought or delisted.
+#[derive(Accounts)]
+pub struct DisallowPaymentMint<'info> {
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"accepted", marketplace.key().as_ref(), accepted_mint.mint.as_ref()],
+        bump = accepted_mint.bump,
+    )]
+    pub accepted_mint: Account<'info,AcceptedMint>,
+}
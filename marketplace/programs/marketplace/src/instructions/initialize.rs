@@ -1,8 +1,8 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
 
 use anchor_spl::token_interface::{Mint,TokenInterface};
 
-use crate::state::marketplace::*;
+use crate::{error::MarketplaceError, state::Marketplace, MAX_FEE_BPS};
 
 #[derive(Accounts)]
 #[instruction(name: String)]
@@ -17,38 +17,61 @@ pub struct Initialize<'info> {
         payer = admin,
         seeds = [b"marketplace", name.as_bytes()],
         bump,
-        space = Marketplace::INIT_SPACE,
+        space = 8 + Marketplace::INIT_SPACE,
     )]
     pub marketplace: Account<'info,Marketplace>,
 
-    #[account(seeds = [b"treasury", marketplace.key().as_ref()], bump)]
-    pub treasury: Account<'info, SystemAccount>,
+    #[account(mut, seeds = [b"treasury", marketplace.key().as_ref()], bump)]
+    pub treasury: SystemAccount<'info>,
 
-    #[account(init, payer = admin, seeds = [b"rewards", marketplace.key().as_ref(), bump, mint::decimals = 6, mint::authority = marketplace])]
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump,
+        mint::decimals = 6,
+        mint::authority = marketplace,
+        mint::token_program = token_program
+    )]
     pub rewards_mint:  InterfaceAccount<'info,Mint>,
 
     pub token_program: Interface<'info,TokenInterface>,
 
 
-    #[account()]
     pub system_program: Program<'info,System>
 }
 
 
 
 impl<'info> Initialize<'info> {
-    pub fn init(&mut self , name: String, fee: u16, bumps: InitializeBumps) -> Result<()> {
-        assert!(name.len() < 32);
+    pub fn init(&mut self, name: String, fee: u16, bumps: &InitializeBumps) -> Result<()> {
+        require!(!name.is_empty() && name.len() <= 32, MarketplaceError::InvalidName);
+        require!(fee <= MAX_FEE_BPS, MarketplaceError::InvalidFee);
         
-        self.marketplace.set_innet(Marketplace {
-            admin: self.admin.keu,
+        self.marketplace.set_inner(Marketplace {
+            admin: self.admin.key(),
+            pending_admin: None,
             fee,
-            bumps: bumps.marketplace,
+            rewards_rate: 0,
+            bump: bumps.marketplace,
             treasury_bump: bumps.treasury,
             rewards_bump: bumps.rewards_mint,
             name
-        })
+        });
         
         Ok(())
     }
+
+    // Seeds the treasury with its rent-exempt minimum so fees smaller than
+    // that can still be paid into it.
+    pub fn fund_treasury(&mut self) -> Result<()> {
+        let lamports = Rent::get()?.minimum_balance(0);
+
+        let cpi_accounts = Transfer {
+            from: self.admin.to_account_info(),
+            to: self.treasury.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+        transfer(cpi_ctx, lamports)
+    }
 }
@@ -1,44 +1,48 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{MasterEditionAccount, Metadata, MetadataAccount},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
 
-use anchor_spl::{token_interface::{Mint,TokenInterface}, Metadata::{metadata_program}};
-use anchor_lang::*;
-
+use crate::{error::MarketplaceError, state::{AcceptedMint, AssetStandard, Listing, Marketplace, SaleKind}};
 
+#[derive(Accounts)]
 pub struct List<'info> {
         
     #[account(mut)]
     pub maker: Signer<'info>,
 
-    #[account(ut,
-    associated_token::mint = maker_mint,
-    associated_token:: authority = maker)]
-    pub maker_ata : InterfaceAccount<'info,TokenAccount>,
-
     #[account(
-        init,
-        payer = maker, 
-        associated_token::mint = maker_mint,
-        associated_token::authority = maker,
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
     )]
-    pub vault: InterfaceAcount<'info,TokenAccount>,
-    
+    pub marketplace: Account<'info,Marketplace>,
 
+    #[account(mint::token_program = token_program)]
+    pub maker_mint: InterfaceAccount<'info,Mint>,
 
+    #[account(mut,
+    associated_token::mint = maker_mint,
+    associated_token::authority = maker,
+    associated_token::token_program = token_program)]
+    pub maker_ata: InterfaceAccount<'info,TokenAccount>,
 
     #[account(
         init,
-        payer = maker,
-        seeds = [marketplace.key().as_ref(), maker_mint.key().as_ref()],
-        bump,
-        space = Listing::INIT_SPACE,
+        payer = maker, 
+        associated_token::mint = maker_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
     )]
-    pub listing: Account<'info, Listing>,
+    pub vault: InterfaceAccount<'info,TokenAccount>,
 
     #[account(
         init,
         payer = maker,
-        seeds = [b"list", name.as_bytes()],
+        seeds = [marketplace.key().as_ref(), maker_mint.key().as_ref()],
         bump,
-        space = Listing::INIT_SPACE,
+        space = 8 + Listing::INIT_SPACE,
     )]
     pub listing: Account<'info,Listing>,
 
@@ -52,70 +56,90 @@ pub struct List<'info> {
         ],
         seeds::program = metadata_program.key(),
         bump,
-        constraint = metadata.collection.as_ref().unwrap().key.as_ref() == collection_mint.key().as_ref(),
-        constraint = metadata.collection.as_ref().unwrap().verified,
+        constraint = metadata.collection.as_ref().is_some_and(|collection| collection.verified && collection.key == collection_mint.key()) @ MarketplaceError::CollectionNotVerified,
     )]
-    pub metadata: Account<'info, Metadata>,
-
-    pub metadata_program: Program<'info,Metadata>,
-
-
-    #[account(seeds = [b"treasury", marketplace.key().as_ref()], bump)]
-    pub treasury: Account<'info, SystemAccount>,
+    pub metadata: Account<'info, MetadataAccount>,
 
     #[account(
         seeds = [
             b"metadata",
             metadata_program.key().as_ref(),
-            maker_mint.key().as_ref
-        ]
+            maker_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
     )]
     pub master_edition: Account<'info,MasterEditionAccount>,
-    pub metadata_progam: Program<'info,Metadata>,
 
+    // Prices the listing in this allowlisted mint; left out for SOL.
     #[account(
-        init,
-        payer = maker,
-        seeds = [b"listing", marketplace.key().as_ref(), maker_mint.key().as_ref()],
-        bump,
-        space = 8 + Listing::INIT_SPACE,
+        seeds = [b"accepted", marketplace.key().as_ref(), accepted_mint.mint.as_ref()],
+        bump = accepted_mint.bump,
     )]
-    pub listing: Account<'info,Listing>,
-
-    #[account(init, payer = admin, seeds = [b"rewards", marketplace.key().as_ref(), bump, mint::decimals = 6, mint::authority = marketplace])]
-    pub rewards_mint:  InterfaceAccount<'info,Mint>,
+    pub accepted_mint: Option<Account<'info,AcceptedMint>>,
 
+    pub metadata_program: Program<'info,Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info,TokenInterface>,
-
-
-    #[account()]
     pub system_program: Program<'info,System>
 }
 
 impl<'info> List<'info>  {
     pub fn create_listing(&mut self, price: u64, bumps: &ListBumps) -> Result<()> {
+        require!(price > 0, MarketplaceError::InvalidPrice);
+
         self.listing.set_inner(Listing {
             maker: self.maker.key(),
             mint: self.maker_mint.key(),
             price,
+            payment_mint: self.accepted_mint.as_ref().map(|accepted| accepted.mint),
+            kind: SaleKind::Fixed,
+            standard: AssetStandard::Legacy,
             bump: bumps.listing,
         });
         Ok(())
     }
 
+    pub fn create_english_auction(&mut self, reserve: u64, min_increment: u64, duration: i64, extension: i64, bumps: &ListBumps) -> Result<()> {
+        require!(duration > 0 && extension >= 0 && min_increment > 0, MarketplaceError::InvalidAuction);
+        require!(self.accepted_mint.is_none(), MarketplaceError::AuctionNotInSol);
+        self.create_listing(reserve, bumps)?;
+
+        self.listing.kind = SaleKind::English {
+            end_ts: Clock::get()?.unix_timestamp + duration,
+            min_increment,
+            extension,
+            highest_bidder: None,
+            highest_bid: 0,
+        };
+        Ok(())
+    }
+
+    pub fn create_dutch_auction(&mut self, start_price: u64, floor_price: u64, duration: i64, bumps: &ListBumps) -> Result<()> {
+        require!(duration > 0 && floor_price < start_price, MarketplaceError::InvalidAuction);
+        self.create_listing(start_price, bumps)?;
+
+        let start_ts = Clock::get()?.unix_timestamp;
+        self.listing.kind = SaleKind::Dutch {
+            start_ts,
+            end_ts: start_ts + duration,
+            floor_price,
+        };
+        Ok(())
+    }
+
     pub fn deposit_nft(&mut self) -> Result<()> {
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = TransferChecked {
             from: self.maker_ata.to_account_info(),
             mint: self.maker_mint.to_account_info(),
-            to: self.maker_mint.to_account_info(),
+            to: self.vault.to_account_info(),
             authority: self.maker.to_account_info(),
         };
 
-        let cp_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        transfer_checked(cpi_ctx, 1, self.maker_mint.decimals)
     }
-
-
-}
\ No newline at end of file
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::Mint;
+
+use crate::{asset::{self, CompressedMetadata, LeafArgs}, error::MarketplaceError, state::{AssetStandard, Listing, Marketplace, SaleKind}};
+
+// Lists a compressed NFT by handing its leaf to the listing. The leaf's proof
+// path comes in as remaining accounts. Its metadata is passed in full and
+// checked against the leaf, which is how the collection is checked.
+#[derive(Accounts)]
+#[instruction(price: u64, leaf: LeafArgs)]
+pub struct ListCompressed<'info> {
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Bubblegum verifies the leaf against this tree.
+    #[account(mut, owner = asset::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Bubblegum's config PDA for the tree.
+    #[account(seeds = [merkle_tree.key().as_ref()], bump, seeds::program = asset::MPL_BUBBLEGUM_ID)]
+    pub tree_config: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds = [marketplace.key().as_ref(), asset::compressed_asset_id(&merkle_tree.key(), leaf.nonce).as_ref()],
+        bump,
+        space = 8 + Listing::INIT_SPACE,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    /// CHECK: the Bubblegum program.
+    #[account(address = asset::MPL_BUBBLEGUM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: the SPL noop program.
+    #[account(address = asset::SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: the SPL account compression program.
+    #[account(address = asset::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> ListCompressed<'info> {
+    pub fn create_listing(&mut self, price: u64, leaf: &LeafArgs, metadata: &CompressedMetadata, bumps: &ListCompressedBumps) -> Result<()> {
+        require!(price > 0, MarketplaceError::InvalidPrice);
+        metadata.verify(leaf)?;
+        require!(metadata.in_collection(&self.collection_mint.key()), MarketplaceError::CollectionNotVerified);
+
+        self.listing.set_inner(Listing {
+            maker: self.maker.key(),
+            mint: asset::compressed_asset_id(&self.merkle_tree.key(), leaf.nonce),
+            price,
+            payment_mint: None,
+            kind: SaleKind::Fixed,
+            standard: AssetStandard::Compressed,
+            bump: bumps.listing,
+        });
+        Ok(())
+    }
+
+    pub fn deposit_nft(&mut self, leaf: &LeafArgs, proof: &[AccountInfo<'info>]) -> Result<()> {
+        asset::bubblegum_transfer(
+            &self.bubblegum_program,
+            &self.tree_config,
+            &self.maker,
+            &self.listing.to_account_info(),
+            &self.merkle_tree,
+            &self.log_wrapper,
+            &self.compression_program,
+            &self.system_program,
+            leaf,
+            proof,
+            &[],
+        )
+    }
+}
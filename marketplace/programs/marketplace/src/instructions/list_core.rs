@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::{asset, error::MarketplaceError, state::{AssetStandard, Listing, Marketplace, SaleKind}};
+
+// Lists a Metaplex Core asset without taking custody: the listing becomes
+// its transfer delegate and moves it to the buyer at purchase.
+#[derive(Accounts)]
+pub struct ListCore<'info> {
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    /// CHECK: read and checked as a Core asset in `create_listing`.
+    #[account(mut, owner = asset::MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+
+    /// CHECK: must be the asset's collection, or the Core program id for an
+    /// asset without one, checked in `create_listing`.
+    #[account(mut)]
+    pub collection: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds = [marketplace.key().as_ref(), asset.key().as_ref()],
+        bump,
+        space = 8 + Listing::INIT_SPACE,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    /// CHECK: the Core program.
+    #[account(address = asset::MPL_CORE_ID)]
+    pub core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> ListCore<'info> {
+    pub fn create_listing(&mut self, price: u64, bumps: &ListCoreBumps) -> Result<()> {
+        require!(price > 0, MarketplaceError::InvalidPrice);
+        let (owner, collection) = asset::core_asset(&self.asset)?;
+        require_keys_eq!(owner, self.maker.key(), MarketplaceError::NotAssetOwner);
+        // Core's own program id stands in for a missing collection, as Core
+        // takes it for its optional accounts.
+        let expected = (self.collection.key() != asset::MPL_CORE_ID).then(|| self.collection.key());
+        require!(collection == expected, MarketplaceError::CollectionNotVerified);
+
+        self.listing.set_inner(Listing {
+            maker: self.maker.key(),
+            mint: self.asset.key(),
+            price,
+            payment_mint: None,
+            kind: SaleKind::Fixed,
+            standard: AssetStandard::Core,
+            bump: bumps.listing,
+        });
+        Ok(())
+    }
+
+    pub fn approve_delegate(&mut self) -> Result<()> {
+        asset::core_approve_transfer_delegate(
+            &self.core_program,
+            &self.asset,
+            &self.collection,
+            &self.maker,
+            &self.system_program,
+            self.listing.key(),
+        )
+    }
+}
@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{freeze_delegated_account, FreezeDelegatedAccount, MasterEditionAccount, Metadata, MetadataAccount},
+    token_interface::{approve, Approve, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::MarketplaceError, state::{AcceptedMint, AssetStandard, Listing, Marketplace, SaleKind}};
+
+// Lists a token-metadata NFT without escrowing it: the listing becomes the
+// delegate on the maker's ATA and freezes it through the master edition, so
+// the NFT stays in the wallet but can't move until it sells or is delisted.
+#[derive(Accounts)]
+pub struct ListDelegated<'info> {
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    #[account(mint::token_program = token_program)]
+    pub maker_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(mut,
+    associated_token::mint = maker_mint,
+    associated_token::authority = maker,
+    associated_token::token_program = token_program)]
+    pub maker_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        init,
+        payer = maker,
+        seeds = [marketplace.key().as_ref(), maker_mint.key().as_ref()],
+        bump,
+        space = 8 + Listing::INIT_SPACE,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+        constraint = metadata.collection.as_ref().is_some_and(|collection| collection.verified && collection.key == collection_mint.key()) @ MarketplaceError::CollectionNotVerified,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub master_edition: Account<'info,MasterEditionAccount>,
+
+    // Prices the listing in this allowlisted mint; left out for SOL.
+    #[account(
+        seeds = [b"accepted", marketplace.key().as_ref(), accepted_mint.mint.as_ref()],
+        bump = accepted_mint.bump,
+    )]
+    pub accepted_mint: Option<Account<'info,AcceptedMint>>,
+
+    pub metadata_program: Program<'info,Metadata>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> ListDelegated<'info> {
+    pub fn create_listing(&mut self, price: u64, bumps: &ListDelegatedBumps) -> Result<()> {
+        require!(price > 0, MarketplaceError::InvalidPrice);
+
+        self.listing.set_inner(Listing {
+            maker: self.maker.key(),
+            mint: self.maker_mint.key(),
+            price,
+            payment_mint: self.accepted_mint.as_ref().map(|accepted| accepted.mint),
+            kind: SaleKind::Fixed,
+            standard: AssetStandard::LegacyDelegated,
+            bump: bumps.listing,
+        });
+        Ok(())
+    }
+
+    pub fn delegate_and_freeze(&mut self) -> Result<()> {
+        let cpi_accounts = Approve {
+            to: self.maker_ata.to_account_info(),
+            delegate: self.listing.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        approve(cpi_ctx, 1)?;
+
+        let marketplace = self.marketplace.key();
+        let mint = self.maker_mint.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        let cpi_accounts = FreezeDelegatedAccount {
+            metadata: self.metadata.to_account_info(),
+            delegate: self.listing.to_account_info(),
+            token_account: self.maker_ata.to_account_info(),
+            edition: self.master_edition.to_account_info(),
+            mint: self.maker_mint.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.metadata_program.to_account_info(), cpi_accounts, &signer_seeds);
+        freeze_delegated_account(cpi_ctx)
+    }
+}
@@ -0,0 +1,164 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer},
+};
+use anchor_spl::{
+    associated_token::{create, get_associated_token_address_with_program_id, AssociatedToken, Create},
+    metadata::{mpl_token_metadata, MasterEditionAccount, Metadata, MetadataAccount},
+    token_interface::{transfer_checked, Mint, TokenInterface, TransferChecked},
+};
+
+use crate::{error::MarketplaceError, state::{AssetStandard, Listing, Marketplace, SaleKind}};
+
+// Accounts each NFT takes in remaining accounts, in order: mint, maker_ata,
+// vault, listing, metadata, master_edition.
+pub const LIST_MANY_ACCOUNTS: usize = 6;
+
+// Lists several NFTs from one collection at fixed SOL prices, the same way
+// `list` does one. It either lists every NFT or none of them.
+#[derive(Accounts)]
+pub struct ListMany<'info> {
+
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    pub metadata_program: Program<'info,Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> ListMany<'info> {
+    pub fn list_many(&mut self, prices: &[u64], items: &'info [AccountInfo<'info>], program_id: &Pubkey) -> Result<()> {
+        require!(
+            !prices.is_empty() && items.len() == prices.len() * LIST_MANY_ACCOUNTS,
+            MarketplaceError::InvalidBatchAccounts
+        );
+
+        for (price, accounts) in prices.iter().zip(items.chunks(LIST_MANY_ACCOUNTS)) {
+            self.list_one(*price, accounts, program_id)?;
+        }
+        Ok(())
+    }
+
+    // Runs the checks `List` makes through its account constraints by hand,
+    // then creates the listing and its vault and deposits the NFT.
+    fn list_one(&mut self, price: u64, accounts: &'info [AccountInfo<'info>], program_id: &Pubkey) -> Result<()> {
+        let [mint, maker_ata, vault, listing, metadata, master_edition] = accounts else {
+            return err!(MarketplaceError::InvalidBatchAccounts);
+        };
+        require!(price > 0, MarketplaceError::InvalidPrice);
+
+        let mint_account = InterfaceAccount::<Mint>::try_from(mint)?;
+        require_keys_eq!(*mint.owner, self.token_program.key(), MarketplaceError::InvalidBatchAccounts);
+        require_keys_eq!(
+            maker_ata.key(),
+            get_associated_token_address_with_program_id(&self.maker.key(), &mint.key(), &self.token_program.key()),
+            MarketplaceError::InvalidBatchAccounts
+        );
+
+        let metadata_seeds = [b"metadata".as_ref(), mpl_token_metadata::ID.as_ref(), mint.key.as_ref()];
+        require_keys_eq!(
+            metadata.key(),
+            Pubkey::find_program_address(&metadata_seeds, &mpl_token_metadata::ID).0,
+            MarketplaceError::InvalidBatchAccounts
+        );
+        let metadata_account = Account::<MetadataAccount>::try_from(metadata)?;
+        require!(
+            metadata_account.collection.as_ref().is_some_and(|collection| collection.verified && collection.key == self.collection_mint.key()),
+            MarketplaceError::CollectionNotVerified
+        );
+
+        let edition_seeds = [b"metadata".as_ref(), mpl_token_metadata::ID.as_ref(), mint.key.as_ref(), b"edition".as_ref()];
+        require_keys_eq!(
+            master_edition.key(),
+            Pubkey::find_program_address(&edition_seeds, &mpl_token_metadata::ID).0,
+            MarketplaceError::InvalidBatchAccounts
+        );
+        Account::<MasterEditionAccount>::try_from(master_edition)?;
+
+        let marketplace = self.marketplace.key();
+        let (listing_key, bump) = Pubkey::find_program_address(&[marketplace.as_ref(), mint.key.as_ref()], program_id);
+        require_keys_eq!(listing.key(), listing_key, MarketplaceError::InvalidBatchAccounts);
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint.key.as_ref(),
+            &[bump]
+        ]];
+
+        self.create_listing_account(listing, &signer_seeds, program_id)?;
+
+        let state = Listing {
+            maker: self.maker.key(),
+            mint: mint.key(),
+            price,
+            payment_mint: None,
+            kind: SaleKind::Fixed,
+            standard: AssetStandard::Legacy,
+            bump,
+        };
+        state.try_serialize(&mut &mut listing.try_borrow_mut_data()?[..])?;
+
+        let cpi_accounts = Create {
+            payer: self.maker.to_account_info(),
+            associated_token: vault.clone(),
+            authority: listing.clone(),
+            mint: mint.clone(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        };
+        create(CpiContext::new(self.associated_token_program.to_account_info(), cpi_accounts))?;
+
+        let cpi_accounts = TransferChecked {
+            from: maker_ata.clone(),
+            mint: mint.clone(),
+            to: vault.clone(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        transfer_checked(cpi_ctx, 1, mint_account.decimals)
+    }
+
+    // Creates the listing account the way Anchor's `init` does, so a listing
+    // address someone has already sent lamports to can still be created:
+    // top it up to rent exemption, then allocate and assign it.
+    fn create_listing_account(&self, listing: &AccountInfo<'info>, signer_seeds: &[&[&[u8]]], program_id: &Pubkey) -> Result<()> {
+        let space = 8 + Listing::INIT_SPACE;
+        let rent = Rent::get()?.minimum_balance(space);
+        let lamports = listing.lamports();
+
+        if lamports == 0 {
+            let cpi_accounts = CreateAccount {
+                from: self.maker.to_account_info(),
+                to: listing.clone(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(self.system_program.to_account_info(), cpi_accounts, signer_seeds);
+            return create_account(cpi_ctx, rent, space as u64, program_id);
+        }
+
+        let top_up = rent.saturating_sub(lamports);
+        if top_up > 0 {
+            let cpi_accounts = Transfer {
+                from: self.maker.to_account_info(),
+                to: listing.clone(),
+            };
+            transfer(CpiContext::new(self.system_program.to_account_info(), cpi_accounts), top_up)?;
+        }
+
+        let cpi_accounts = Allocate { account_to_allocate: listing.clone() };
+        let cpi_ctx = CpiContext::new_with_signer(self.system_program.to_account_info(), cpi_accounts, signer_seeds);
+        allocate(cpi_ctx, space as u64)?;
+
+        let cpi_accounts = Assign { account_to_assign: listing.clone() };
+        let cpi_ctx = CpiContext::new_with_signer(self.system_program.to_account_info(), cpi_accounts, signer_seeds);
+        assign(cpi_ctx, program_id)
+    }
+}
@@ -1,11 +1,77 @@
 pub mod initialize;
-pub use initialize;
-
-pub mod marketplace;
-pub use marketplace;
+pub use initialize::*;
 
 pub mod list;
-pub use list;
+pub use list::*;
+
+pub mod delist;
+pub use delist::*;
 
 pub mod purchase;
-pub use purchase;
\ No newline at end of file
+pub use purchase::*;
+
+pub mod set_rewards_rate;
+pub use set_rewards_rate::*;
+
+pub mod withdraw_treasury;
+pub use withdraw_treasury::*;
+
+pub mod withdraw_treasury_tokens;
+pub use withdraw_treasury_tokens::*;
+
+pub mod update_fee;
+pub use update_fee::*;
+
+pub mod transfer_admin;
+pub use transfer_admin::*;
+
+pub mod accept_admin;
+pub use accept_admin::*;
+
+pub mod allow_payment_mint;
+pub use allow_payment_mint::*;
+
+pub mod disallow_payment_mint;
+pub use disallow_payment_mint::*;
+
+pub mod place_bid;
+pub use place_bid::*;
+
+pub mod cancel_bid;
+pub use cancel_bid::*;
+
+pub mod accept_bid;
+pub use accept_bid::*;
+
+pub mod bid_on_auction;
+pub use bid_on_auction::*;
+
+pub mod settle_auction;
+pub use settle_auction::*;
+
+pub mod list_core;
+pub use list_core::*;
+
+pub mod delist_core;
+pub use delist_core::*;
+
+pub mod purchase_core;
+pub use purchase_core::*;
+
+pub mod list_compressed;
+pub use list_compressed::*;
+
+pub mod delist_compressed;
+pub use delist_compressed::*;
+
+pub mod purchase_compressed;
+pub use purchase_compressed::*;
+
+pub mod list_delegated;
+pub use list_delegated::*;
+
+pub mod list_many;
+pub use list_many::*;
+
+pub mod sweep;
+pub use sweep::*;
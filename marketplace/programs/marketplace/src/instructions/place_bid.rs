@@ -0,0 +1,106 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::MarketplaceError, payment, state::{AcceptedMint, Bid, Marketplace}};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct PlaceBid<'info> {
+
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = bidder,
+        seeds = [b"bid", marketplace.key().as_ref(), bidder.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Bid::INIT_SPACE,
+    )]
+    pub bid: Account<'info,Bid>,
+
+    // The accounts below are only passed for bids in an allowlisted token.
+    #[account(
+        seeds = [b"accepted", marketplace.key().as_ref(), accepted_mint.mint.as_ref()],
+        bump = accepted_mint.bump,
+    )]
+    pub accepted_mint: Option<Account<'info,AcceptedMint>>,
+
+    #[account(mint::token_program = payment_token_program)]
+    pub payment_mint: Option<InterfaceAccount<'info,Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = bidder,
+        associated_token::token_program = payment_token_program
+    )]
+    pub bidder_payment_ata: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    #[account(
+        init,
+        payer = bidder,
+        associated_token::mint = payment_mint,
+        associated_token::authority = bid,
+        associated_token::token_program = payment_token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    pub payment_token_program: Option<Interface<'info,TokenInterface>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> PlaceBid<'info> {
+    pub fn place_bid(&mut self, seed: u64, price: u64, mint: Option<Pubkey>, bumps: &PlaceBidBumps) -> Result<()> {
+        require!(price > 0, MarketplaceError::InvalidPrice);
+
+        self.bid.set_inner(Bid {
+            bidder: self.bidder.key(),
+            seed,
+            collection: self.collection_mint.key(),
+            mint,
+            payment_mint: self.accepted_mint.as_ref().map(|accepted| accepted.mint),
+            price,
+            bump: bumps.bid,
+        });
+        Ok(())
+    }
+
+    pub fn deposit(&mut self) -> Result<()> {
+        let Some(accepted_mint) = &self.accepted_mint else {
+            let cpi_accounts = Transfer {
+                from: self.bidder.to_account_info(),
+                to: self.bid.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+            return transfer(cpi_ctx, self.bid.price);
+        };
+
+        match (&self.payment_mint, &self.bidder_payment_ata, &self.vault, &self.payment_token_program) {
+            (Some(mint), Some(from), Some(vault), Some(token_program)) if mint.key() == accepted_mint.mint => payment::transfer_tokens(
+                &token_program.to_account_info(),
+                &from.to_account_info(),
+                &mint.to_account_info(),
+                &vault.to_account_info(),
+                &self.bidder.to_account_info(),
+                self.bid.price,
+                mint.decimals,
+                &[],
+            ),
+            _ => err!(MarketplaceError::PaymentMintMismatch),
+        }
+    }
+}
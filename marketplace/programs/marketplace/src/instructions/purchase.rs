@@ -1,11 +1,13 @@
 use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{thaw_delegated_account, MasterEditionAccount, Metadata, MetadataAccount, ThawDelegatedAccount},
+    token_interface::{close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
 
-use anchor_spl::token_interface::{Mint,TokenInterface};
-
-use crate::state::marketplace::*;
+use crate::{error::MarketplaceError, payment, rewards, state::{AssetStandard, Listing, Marketplace}};
 
 #[derive(Accounts)]
-#[instruction(name: String)]
 pub struct Purchase<'info> {
     
     #[account(mut)]
@@ -14,44 +16,274 @@ pub struct Purchase<'info> {
     #[account(mut)]
     pub maker: SystemAccount<'info>,
 
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    #[account(mint::token_program = token_program)]
+    pub maker_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = maker_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [marketplace.key().as_ref(), maker_mint.key().as_ref()],
+        bump = listing.bump,
+        constraint = listing.payment_mint == payment_mint.as_ref().map(|mint| mint.key()) @ MarketplaceError::PaymentMintMismatch,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    // Holds the NFT for an escrowed listing.
+    #[account(
+        mut,
+        associated_token::mint = maker_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    // The frozen ATA and the edition that thaws it, for a delegated listing.
+    #[account(
+        mut,
+        associated_token::mint = maker_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata: Option<InterfaceAccount<'info,TokenAccount>>,
 
     #[account(
-        init,
-        payer = admin,
-        seeds = [b"marketplace", marketplace.key().as_ref()],
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = metadata_program.key(),
         bump,
-        space = Marketplace::INIT_SPACE,
     )]
-    pub marketplace: Account<'info,Marketplace>,
+    pub master_edition: Option<Account<'info,MasterEditionAccount>>,
 
-    #[account(seeds = [b"treasury", marketplace.key().as_ref()], bump)]
-    pub treasury: Account<'info, SystemAccount>,
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
 
-    #[account(init, payer = admin, seeds = [b"rewards", marketplace.key().as_ref(), bump, mint::decimals = 6, mint::authority = marketplace])]
-    pub rewards_mint:  InterfaceAccount<'info,Mint>,
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump,
+    )]
+    pub treasury: SystemAccount<'info>,
 
-    pub token_program: Interface<'info,TokenInterface>,
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+        mint::token_program = token_program
+    )]
+    pub rewards_mint: InterfaceAccount<'info,Mint>,
 
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_rewards_ata: InterfaceAccount<'info,TokenAccount>,
 
-    #[account()]
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    // The accounts below are only passed for listings priced in a token.
+    #[account(mint::token_program = payment_token_program)]
+    pub payment_mint: Option<InterfaceAccount<'info,Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = payment_token_program
+    )]
+    pub taker_payment_ata: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = payment_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = payment_token_program
+    )]
+    pub maker_payment_ata: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = payment_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = payment_token_program
+    )]
+    pub treasury_payment_ata: Option<InterfaceAccount<'info,TokenAccount>>,
+
+    pub payment_token_program: Option<Interface<'info,TokenInterface>>,
+
+    pub metadata_program: Program<'info,Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
     pub system_program: Program<'info,System>
 }
 
 
 
 impl<'info> Purchase<'info> {
-    pub fn init(&mut self , name: String, fee: u16, bumps: InitializeBumps) -> Result<()> {
-        assert!(name.len() < 32);
-        
-        self.marketplace.set_innet(Marketplace {
-            admin: self.admin.keu,
-            fee,
-            bumps: bumps.marketplace,
-            treasury_bump: bumps.treasury,
-            rewards_bump: bumps.rewards_mint,
-            name
-        })
-        
+    // The maker gets the sale price minus the marketplace fee, which goes to
+    // the treasury, and minus the creator royalties. Royalty recipients come
+    // in as remaining accounts, in metadata order; an unverified creator's
+    // share stays with the maker.
+    pub fn pay(&mut self, creators: &[AccountInfo<'info>]) -> Result<()> {
+        let price = self.listing.current_price(Clock::get()?.unix_timestamp)?;
+        let fee = self.marketplace.fee_on(price)?;
+        let payment_mint = match (&self.payment_mint, &self.payment_token_program) {
+            (Some(mint), Some(token_program)) => Some((mint.key(), token_program.key())),
+            _ => None,
+        };
+        let royalties = payment::royalties(&self.metadata, price, payment_mint, creators)?;
+        let royalty_total = royalties.iter().map(|(_, amount)| amount).sum::<u64>();
+        let deductions = fee.checked_add(royalty_total).ok_or(MarketplaceError::Overflow)?;
+        let proceeds = price.checked_sub(deductions).ok_or(MarketplaceError::Overflow)?;
+
+        let (maker, treasury) = match (&self.maker_payment_ata, &self.treasury_payment_ata) {
+            (Some(maker), Some(treasury)) => (maker.to_account_info(), treasury.to_account_info()),
+            _ => (self.maker.to_account_info(), self.treasury.to_account_info()),
+        };
+        for (to, amount) in royalties.into_iter().chain([(maker, proceeds), (treasury, fee)]) {
+            self.transfer_payment(to, amount)?;
+        }
         Ok(())
     }
+
+    // Moves `amount` of the listing's currency from the taker; every token
+    // account has to be there for a token-priced listing.
+    fn transfer_payment(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        if self.listing.payment_mint.is_none() {
+            return payment::transfer_lamports(&self.system_program.to_account_info(), &self.taker.to_account_info(), &to, amount, &[]);
+        }
+
+        match (&self.payment_mint, &self.taker_payment_ata, &self.maker_payment_ata, &self.treasury_payment_ata, &self.payment_token_program) {
+            (Some(mint), Some(from), Some(_), Some(_), Some(token_program)) => payment::transfer_tokens(
+                &token_program.to_account_info(),
+                &from.to_account_info(),
+                &mint.to_account_info(),
+                &to,
+                &self.taker.to_account_info(),
+                amount,
+                mint.decimals,
+                &[],
+            ),
+            _ => err!(MarketplaceError::PaymentMintMismatch),
+        }
+    }
+
+    // Both sides of a SOL sale earn rewards at the rate the admin has set.
+    pub fn mint_rewards(&mut self) -> Result<()> {
+        if self.payment_mint.is_some() {
+            return Ok(());
+        }
+        let price = self.listing.current_price(Clock::get()?.unix_timestamp)?;
+        rewards::mint_rewards(
+            &self.marketplace,
+            &self.rewards_mint.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.taker.key(),
+            &self.maker.key(),
+            [self.taker_rewards_ata.to_account_info(), self.maker_rewards_ata.to_account_info()],
+            price,
+        )
+    }
+
+    pub fn transfer_nft(&mut self) -> Result<()> {
+        let marketplace = self.marketplace.key();
+        let mint = self.maker_mint.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        // A delegated listing thaws the maker's ATA and moves the NFT out of
+        // it as delegate; an escrowed one empties its vault.
+        let from = match (self.listing.standard, &self.vault, &self.maker_ata, &self.master_edition) {
+            (AssetStandard::Legacy, Some(vault), _, _) => vault.to_account_info(),
+            (AssetStandard::LegacyDelegated, _, Some(maker_ata), Some(master_edition)) => {
+                let cpi_accounts = ThawDelegatedAccount {
+                    metadata: self.metadata.to_account_info(),
+                    delegate: self.listing.to_account_info(),
+                    token_account: maker_ata.to_account_info(),
+                    edition: master_edition.to_account_info(),
+                    mint: self.maker_mint.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(self.metadata_program.to_account_info(), cpi_accounts, &signer_seeds);
+                thaw_delegated_account(cpi_ctx)?;
+                maker_ata.to_account_info()
+            }
+            _ => return err!(MarketplaceError::ListingAccountsMismatch),
+        };
+
+        let cpi_accounts = TransferChecked {
+            from,
+            mint: self.maker_mint.to_account_info(),
+            to: self.taker_ata.to_account_info(),
+            authority: self.listing.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+        transfer_checked(cpi_ctx, 1, self.maker_mint.decimals)
+    }
+
+    pub fn close_vault(&mut self) -> Result<()> {
+        let Some(vault) = &self.vault else {
+            return Ok(());
+        };
+
+        let marketplace = self.marketplace.key();
+        let mint = self.maker_mint.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        let close_accounts = CloseAccount {
+            account: vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.listing.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), close_accounts, &signer_seeds);
+        close_account(cpi_ctx)
+    }
 }
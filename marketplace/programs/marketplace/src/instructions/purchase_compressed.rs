@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{asset::{self, CompressedMetadata, LeafArgs}, error::MarketplaceError, payment, rewards, state::{AssetStandard, Listing, Marketplace}};
+
+#[derive(Accounts)]
+#[instruction(leaf: LeafArgs)]
+pub struct PurchaseCompressed<'info> {
+
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    /// CHECK: Bubblegum verifies the leaf against this tree.
+    #[account(mut, owner = asset::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Bubblegum's config PDA for the tree.
+    #[account(seeds = [merkle_tree.key().as_ref()], bump, seeds::program = asset::MPL_BUBBLEGUM_ID)]
+    pub tree_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [marketplace.key().as_ref(), asset::compressed_asset_id(&merkle_tree.key(), leaf.nonce).as_ref()],
+        bump = listing.bump,
+        constraint = listing.standard == AssetStandard::Compressed @ MarketplaceError::NotForSale,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+        mint::token_program = token_program
+    )]
+    pub rewards_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    /// CHECK: the Bubblegum program.
+    #[account(address = asset::MPL_BUBBLEGUM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: the SPL noop program.
+    #[account(address = asset::SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: the SPL account compression program.
+    #[account(address = asset::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> PurchaseCompressed<'info> {
+    // Pays out like `purchase`, with royalties going to the verified creators
+    // in `metadata`. Their wallets lead the remaining accounts, in metadata
+    // order, and the leaf's proof path, which is returned, follows.
+    pub fn pay<'a>(&mut self, leaf: &LeafArgs, metadata: &CompressedMetadata, remaining: &'a [AccountInfo<'info>]) -> Result<&'a [AccountInfo<'info>]> {
+        metadata.verify(leaf)?;
+        let verified = metadata.verified_creators();
+        require!(remaining.len() >= verified.len(), MarketplaceError::InvalidCreatorAccounts);
+        let (creators, proof) = remaining.split_at(verified.len());
+
+        let price = self.listing.price;
+        let fee = self.marketplace.fee_on(price)?;
+        let royalties = payment::split_royalties(metadata.seller_fee_basis_points, &verified, price, None, creators)?;
+        let royalty_total = royalties.iter().map(|(_, amount)| amount).sum::<u64>();
+        let deductions = fee.checked_add(royalty_total).ok_or(MarketplaceError::Overflow)?;
+        let proceeds = price.checked_sub(deductions).ok_or(MarketplaceError::Overflow)?;
+        for (to, amount) in royalties.into_iter().chain([(self.maker.to_account_info(), proceeds), (self.treasury.to_account_info(), fee)]) {
+            payment::transfer_lamports(&self.system_program.to_account_info(), &self.taker.to_account_info(), &to, amount, &[])?;
+        }
+        Ok(proof)
+    }
+
+    pub fn mint_rewards(&mut self) -> Result<()> {
+        rewards::mint_rewards(
+            &self.marketplace,
+            &self.rewards_mint.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.taker.key(),
+            &self.maker.key(),
+            [self.taker_rewards_ata.to_account_info(), self.maker_rewards_ata.to_account_info()],
+            self.listing.price,
+        )
+    }
+
+    pub fn transfer_nft(&mut self, leaf: &LeafArgs, proof: &[AccountInfo<'info>]) -> Result<()> {
+        let marketplace = self.marketplace.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            self.listing.mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        asset::bubblegum_transfer(
+            &self.bubblegum_program,
+            &self.tree_config,
+            &self.listing.to_account_info(),
+            &self.taker,
+            &self.merkle_tree,
+            &self.log_wrapper,
+            &self.compression_program,
+            &self.system_program,
+            leaf,
+            proof,
+            &signer_seeds,
+        )
+    }
+}
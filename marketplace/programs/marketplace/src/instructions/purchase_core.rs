@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{asset, error::MarketplaceError, payment, rewards, state::{AssetStandard, Listing, Marketplace}};
+
+#[derive(Accounts)]
+pub struct PurchaseCore<'info> {
+
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    /// CHECK: the listed asset, pinned by the listing's seeds.
+    #[account(mut, owner = asset::MPL_CORE_ID)]
+    pub asset: UncheckedAccount<'info>,
+
+    /// CHECK: the asset's collection, or the Core program id if it has none;
+    /// Core checks it.
+    #[account(mut)]
+    pub collection: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [marketplace.key().as_ref(), asset.key().as_ref()],
+        bump = listing.bump,
+        constraint = listing.standard == AssetStandard::Core @ MarketplaceError::NotForSale,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+        mint::token_program = token_program
+    )]
+    pub rewards_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    /// CHECK: the Core program.
+    #[account(address = asset::MPL_CORE_ID)]
+    pub core_program: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> PurchaseCore<'info> {
+    // Core keeps royalties in a plugin on the asset or its collection, which
+    // this program doesn't read, so a Core sale pays the fee but no royalties.
+    pub fn pay(&mut self) -> Result<()> {
+        let fee = self.marketplace.fee_on(self.listing.price)?;
+        payment::pay_lamports(
+            &self.system_program.to_account_info(),
+            &self.taker.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.treasury.to_account_info(),
+            self.listing.price,
+            fee,
+        )
+    }
+
+    pub fn mint_rewards(&mut self) -> Result<()> {
+        rewards::mint_rewards(
+            &self.marketplace,
+            &self.rewards_mint.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.taker.key(),
+            &self.maker.key(),
+            [self.taker_rewards_ata.to_account_info(), self.maker_rewards_ata.to_account_info()],
+            self.listing.price,
+        )
+    }
+
+    pub fn transfer_asset(&mut self) -> Result<()> {
+        let marketplace = self.marketplace.key();
+        let asset = self.asset.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            asset.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        asset::core_transfer(
+            &self.core_program,
+            &self.asset,
+            &self.collection,
+            &self.taker,
+            &self.listing.to_account_info(),
+            &self.taker,
+            &self.system_program,
+            &signer_seeds,
+        )
+    }
+}
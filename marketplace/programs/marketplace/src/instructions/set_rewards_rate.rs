@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, state::Marketplace, MAX_REWARDS_RATE};
+
+#[derive(Accounts)]
+pub struct SetRewardsRate<'info> {
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+}
+
+impl<'info> SetRewardsRate<'info> {
+    pub fn set_rewards_rate(&mut self, rewards_rate: u64) -> Result<()> {
+        require!(rewards_rate <= MAX_REWARDS_RATE, MarketplaceError::InvalidRewardsRate);
+        self.marketplace.rewards_rate = rewards_rate;
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{Metadata, MetadataAccount},
+    token_interface::{close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::MarketplaceError, payment, rewards, state::{Listing, Marketplace, SaleKind}};
+
+// Anyone can settle an English auction once it has ended. The NFT goes to
+// the highest bidder, or back to the maker if nobody bid.
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+
+    #[account(mut)]
+    pub settler: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    pub winner: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    #[account(mint::token_program = token_program)]
+    pub maker_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = maker_mint,
+        associated_token::authority = winner,
+        associated_token::token_program = token_program
+    )]
+    pub winner_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [marketplace.key().as_ref(), maker_mint.key().as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info,Listing>,
+
+    #[account(
+        mut,
+        associated_token::mint = maker_mint,
+        associated_token::authority = listing,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            metadata_program.key().as_ref(),
+            maker_mint.key().as_ref(),
+        ],
+        seeds::program = metadata_program.key(),
+        bump,
+    )]
+    pub metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+        mint::token_program = token_program
+    )]
+    pub rewards_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = winner,
+        associated_token::token_program = token_program
+    )]
+    pub winner_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    pub metadata_program: Program<'info,Metadata>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> SettleAuction<'info> {
+    // Pays out the winning bid like a purchase: fee to the treasury,
+    // royalties to the verified creators (remaining accounts, in metadata
+    // order) and the rest to the maker.
+    pub fn pay(&mut self, creators: &[AccountInfo<'info>]) -> Result<()> {
+        let SaleKind::English { end_ts, highest_bidder, highest_bid, .. } = self.listing.kind else {
+            return err!(MarketplaceError::NotForSale);
+        };
+        require!(Clock::get()?.unix_timestamp >= end_ts, MarketplaceError::AuctionNotEnded);
+        require_keys_eq!(highest_bidder.unwrap_or(self.maker.key()), self.winner.key(), MarketplaceError::HighestBidderMismatch);
+        if highest_bidder.is_none() {
+            return Ok(());
+        }
+
+        let fee = self.marketplace.fee_on(highest_bid)?;
+        let royalties = payment::royalties(&self.metadata, highest_bid, None, creators)?;
+        let royalty_total = royalties.iter().map(|(_, amount)| amount).sum::<u64>();
+        let deductions = fee.checked_add(royalty_total).ok_or(MarketplaceError::Overflow)?;
+        let proceeds = highest_bid.checked_sub(deductions).ok_or(MarketplaceError::Overflow)?;
+
+        // The bid is held in the listing account itself.
+        for (to, amount) in royalties.into_iter().chain([(self.maker.to_account_info(), proceeds), (self.treasury.to_account_info(), fee)]) {
+            self.listing.sub_lamports(amount)?;
+            to.add_lamports(amount)?;
+        }
+        Ok(())
+    }
+
+    // The winner and the maker earn rewards on the winning bid. An auction
+    // nobody bid on earns nothing.
+    pub fn mint_rewards(&mut self) -> Result<()> {
+        let SaleKind::English { highest_bidder: Some(_), highest_bid, .. } = self.listing.kind else {
+            return Ok(());
+        };
+        rewards::mint_rewards(
+            &self.marketplace,
+            &self.rewards_mint.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.winner.key(),
+            &self.maker.key(),
+            [self.winner_rewards_ata.to_account_info(), self.maker_rewards_ata.to_account_info()],
+            highest_bid,
+        )
+    }
+
+    pub fn transfer_nft(&mut self) -> Result<()> {
+        let marketplace = self.marketplace.key();
+        let mint = self.maker_mint.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.maker_mint.to_account_info(),
+            to: self.winner_ata.to_account_info(),
+            authority: self.listing.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+        transfer_checked(cpi_ctx, 1, self.maker_mint.decimals)
+    }
+
+    pub fn close_vault(&mut self) -> Result<()> {
+        let marketplace = self.marketplace.key();
+        let mint = self.maker_mint.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint.as_ref(),
+            &[self.listing.bump]
+        ]];
+
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.maker.to_account_info(),
+            authority: self.listing.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), close_accounts, &signer_seeds);
+        close_account(cpi_ctx)
+    }
+}
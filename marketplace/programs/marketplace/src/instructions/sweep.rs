@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create},
+    metadata::{mpl_token_metadata, MetadataAccount},
+    token_interface::{close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::MarketplaceError, payment, rewards, state::{AssetStandard, Listing, Marketplace, SaleKind}};
+
+// Accounts each candidate listing takes in remaining accounts, in order:
+// listing, maker, mint, vault, taker_ata, metadata, maker_rewards_ata. The
+// wallets of the NFT's verified creators follow, in metadata order, as
+// `purchase` takes them.
+pub const SWEEP_ACCOUNTS: usize = 7;
+
+// Buys up to `max_count` of the candidate listings from one collection,
+// cheapest first, without spending more than `max_spend` lamports. Swept
+// purchases pay fees and royalties and earn rewards like `purchase`.
+// Candidates a sweep can't buy, such as English auctions or token-priced
+// listings, are skipped.
+#[derive(Accounts)]
+pub struct Sweep<'info> {
+
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    pub collection_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"rewards", marketplace.key().as_ref()],
+        bump = marketplace.rewards_bump,
+        mint::token_program = token_program
+    )]
+    pub rewards_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = rewards_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_rewards_ata: InterfaceAccount<'info,TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+struct Candidate<'info> {
+    listing: Account<'info, Listing>,
+    maker: &'info AccountInfo<'info>,
+    mint: InterfaceAccount<'info, Mint>,
+    vault: &'info AccountInfo<'info>,
+    taker_ata: &'info AccountInfo<'info>,
+    metadata: Account<'info, MetadataAccount>,
+    maker_rewards_ata: &'info AccountInfo<'info>,
+    creators: &'info [AccountInfo<'info>],
+    price: u64,
+}
+
+impl<'info> Sweep<'info> {
+    pub fn sweep(&mut self, max_count: u8, max_spend: u64, items: &'info [AccountInfo<'info>], program_id: &Pubkey) -> Result<()> {
+        let mut candidates = self.candidates(items, program_id)?;
+        candidates.sort_by_key(|candidate| candidate.price);
+
+        let mut spent: u64 = 0;
+        let mut bought = 0;
+        for candidate in candidates {
+            let total = spent.checked_add(candidate.price).ok_or(MarketplaceError::Overflow)?;
+            if bought == max_count || total > max_spend {
+                break;
+            }
+            self.buy(candidate)?;
+            spent = total;
+            bought += 1;
+        }
+        require!(bought > 0, MarketplaceError::NothingToSweep);
+        Ok(())
+    }
+
+    // Splits remaining accounts into candidates, checking each the way
+    // `Purchase` constrains its accounts.
+    fn candidates(&self, mut items: &'info [AccountInfo<'info>], program_id: &Pubkey) -> Result<Vec<Candidate<'info>>> {
+        let now = Clock::get()?.unix_timestamp;
+        let marketplace = self.marketplace.key();
+        let mut candidates = Vec::new();
+
+        while !items.is_empty() {
+            let [listing, maker, mint, vault, taker_ata, metadata, maker_rewards_ata, rest @ ..] = items else {
+                return err!(MarketplaceError::InvalidBatchAccounts);
+            };
+
+            let listing = Account::<Listing>::try_from(listing)?;
+            require_keys_eq!(
+                listing.key(),
+                Pubkey::create_program_address(&[marketplace.as_ref(), listing.mint.as_ref(), &[listing.bump]], program_id)
+                    .map_err(|_| MarketplaceError::InvalidBatchAccounts)?,
+                MarketplaceError::InvalidBatchAccounts
+            );
+            require_keys_eq!(listing.maker, maker.key(), MarketplaceError::InvalidBatchAccounts);
+            require_keys_eq!(listing.mint, mint.key(), MarketplaceError::InvalidBatchAccounts);
+            require_keys_eq!(
+                maker_rewards_ata.key(),
+                get_associated_token_address_with_program_id(&maker.key(), &self.rewards_mint.key(), &self.token_program.key()),
+                MarketplaceError::InvalidBatchAccounts
+            );
+
+            let mint = InterfaceAccount::<Mint>::try_from(mint)?;
+            require_keys_eq!(*mint.to_account_info().owner, self.token_program.key(), MarketplaceError::InvalidBatchAccounts);
+            require_keys_eq!(
+                vault.key(),
+                get_associated_token_address_with_program_id(&listing.key(), &mint.key(), &self.token_program.key()),
+                MarketplaceError::InvalidBatchAccounts
+            );
+
+            let metadata_seeds = [b"metadata".as_ref(), mpl_token_metadata::ID.as_ref(), listing.mint.as_ref()];
+            require_keys_eq!(
+                metadata.key(),
+                Pubkey::find_program_address(&metadata_seeds, &mpl_token_metadata::ID).0,
+                MarketplaceError::InvalidBatchAccounts
+            );
+            let metadata = Account::<MetadataAccount>::try_from(metadata)?;
+            require!(
+                metadata.collection.as_ref().is_some_and(|collection| collection.verified && collection.key == self.collection_mint.key()),
+                MarketplaceError::CollectionNotVerified
+            );
+
+            let creator_count = metadata.creators.iter().flatten().filter(|creator| creator.verified).count();
+            require!(rest.len() >= creator_count, MarketplaceError::InvalidBatchAccounts);
+            let (creators, rest) = rest.split_at(creator_count);
+            items = rest;
+
+            let for_sale = listing.standard == AssetStandard::Legacy
+                && listing.payment_mint.is_none()
+                && !matches!(listing.kind, SaleKind::English { .. });
+            if !for_sale {
+                continue;
+            }
+            let price = listing.current_price(now)?;
+            candidates.push(Candidate { listing, maker, mint, vault, taker_ata, metadata, maker_rewards_ata, creators, price });
+        }
+        Ok(candidates)
+    }
+
+    fn buy(&mut self, candidate: Candidate<'info>) -> Result<()> {
+        let Candidate { listing, maker, mint, vault, taker_ata, metadata, maker_rewards_ata, creators, price } = candidate;
+
+        let fee = self.marketplace.fee_on(price)?;
+        let royalties = payment::royalties(&metadata, price, None, creators)?;
+        let royalty_total = royalties.iter().map(|(_, amount)| amount).sum::<u64>();
+        let deductions = fee.checked_add(royalty_total).ok_or(MarketplaceError::Overflow)?;
+        let proceeds = price.checked_sub(deductions).ok_or(MarketplaceError::Overflow)?;
+        for (to, amount) in royalties.into_iter().chain([(maker.clone(), proceeds), (self.treasury.to_account_info(), fee)]) {
+            payment::transfer_lamports(&self.system_program.to_account_info(), &self.taker.to_account_info(), &to, amount, &[])?;
+        }
+
+        let cpi_accounts = Create {
+            payer: self.taker.to_account_info(),
+            associated_token: maker_rewards_ata.clone(),
+            authority: maker.clone(),
+            mint: self.rewards_mint.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        };
+        create_idempotent(CpiContext::new(self.associated_token_program.to_account_info(), cpi_accounts))?;
+        rewards::mint_rewards(
+            &self.marketplace,
+            &self.rewards_mint.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.taker.key(),
+            maker.key,
+            [self.taker_rewards_ata.to_account_info(), maker_rewards_ata.clone()],
+            price,
+        )?;
+
+        let cpi_accounts = Create {
+            payer: self.taker.to_account_info(),
+            associated_token: taker_ata.clone(),
+            authority: self.taker.to_account_info(),
+            mint: mint.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        };
+        create_idempotent(CpiContext::new(self.associated_token_program.to_account_info(), cpi_accounts))?;
+
+        let marketplace = self.marketplace.key();
+        let mint_key = mint.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            marketplace.as_ref(),
+            mint_key.as_ref(),
+            &[listing.bump]
+        ]];
+
+        let cpi_accounts = TransferChecked {
+            from: vault.clone(),
+            mint: mint.to_account_info(),
+            to: taker_ata.clone(),
+            authority: listing.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, &signer_seeds);
+        transfer_checked(cpi_ctx, 1, mint.decimals)?;
+
+        let close_accounts = CloseAccount {
+            account: vault.clone(),
+            destination: maker.clone(),
+            authority: listing.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), close_accounts, &signer_seeds);
+        close_account(cpi_ctx)?;
+
+        listing.close(maker.clone())
+    }
+}
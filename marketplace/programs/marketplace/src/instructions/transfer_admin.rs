@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Marketplace;
+
+#[derive(Accounts)]
+pub struct TransferAdmin<'info> {
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+}
+
+impl<'info> TransferAdmin<'info> {
+    // Only proposes `new_admin`; the current admin keeps its rights until
+    // `new_admin` accepts, and can propose someone else in the meantime.
+    pub fn transfer_admin(&mut self, new_admin: Pubkey) -> Result<()> {
+        self.marketplace.pending_admin = Some(new_admin);
+        Ok(())
+    }
+}
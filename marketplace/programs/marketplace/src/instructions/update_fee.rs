@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::MarketplaceError, state::Marketplace, MAX_FEE_BPS};
+
+#[derive(Accounts)]
+pub struct UpdateFee<'info> {
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+}
+
+impl<'info> UpdateFee<'info> {
+    pub fn update_fee(&mut self, fee: u16) -> Result<()> {
+        require!(fee <= MAX_FEE_BPS, MarketplaceError::InvalidFee);
+        self.marketplace.fee = fee;
+        Ok(())
+    }
+}
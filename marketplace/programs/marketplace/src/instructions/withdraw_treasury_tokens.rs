@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::MarketplaceError, payment, state::Marketplace};
+
+// Withdraws fees collected in a payment mint, which sit in the treasury's
+// ATA for that mint rather than in the treasury itself.
+#[derive(Accounts)]
+pub struct WithdrawTreasuryTokens<'info> {
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin,
+        seeds = [b"marketplace", marketplace.name.as_bytes()],
+        bump = marketplace.bump,
+    )]
+    pub marketplace: Account<'info,Marketplace>,
+
+    #[account(
+        seeds = [b"treasury", marketplace.key().as_ref()],
+        bump = marketplace.treasury_bump,
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mint::token_program = token_program)]
+    pub payment_mint: InterfaceAccount<'info,Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program
+    )]
+    pub treasury_payment_ata: InterfaceAccount<'info,TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = payment_mint,
+        associated_token::authority = admin,
+        associated_token::token_program = token_program
+    )]
+    pub admin_payment_ata: InterfaceAccount<'info,TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info,TokenInterface>,
+    pub system_program: Program<'info,System>
+}
+
+impl<'info> WithdrawTreasuryTokens<'info> {
+    pub fn withdraw_treasury_tokens(&mut self, amount: u64) -> Result<()> {
+        require!(amount <= self.treasury_payment_ata.amount, MarketplaceError::InsufficientTreasury);
+
+        let marketplace = self.marketplace.key();
+        let signer_seeds: [&[&[u8]] ;1] = [&[
+            b"treasury",
+            marketplace.as_ref(),
+            &[self.marketplace.treasury_bump]
+        ]];
+
+        payment::transfer_tokens(
+            &self.token_program.to_account_info(),
+            &self.treasury_payment_ata.to_account_info(),
+            &self.payment_mint.to_account_info(),
+            &self.admin_payment_ata.to_account_info(),
+            &self.treasury.to_account_info(),
+            amount,
+            self.payment_mint.decimals,
+            &signer_seeds,
+        )
+    }
+}
@@ -1,9 +1,16 @@
 #![allow(unexpected_cfgs)]
 use anchor_lang::prelude::*;
-mod instructions;
-mod state;
+pub mod asset;
+pub mod constants;
+pub mod error;
+pub mod instructions;
+pub mod payment;
+pub mod rewards;
+pub mod state;
 
-use crate::instructions::*;
+pub use asset::{CompressedMetadata, LeafArgs};
+pub use constants::*;
+pub use instructions::*;
 
 
 declare_id!("AUavZMdqDrCBaqbJbEknEAobuTfKi2SA7KPfQXyRE2cd");
@@ -12,23 +19,137 @@ declare_id!("AUavZMdqDrCBaqbJbEknEAobuTfKi2SA7KPfQXyRE2cd");
 pub mod marketplace {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        msg!("Greetings from: {:?}", ctx.program_id);
-        Ok(())
+    pub fn initialize(ctx: Context<Initialize>, name: String, fee: u16) -> Result<()> {
+        ctx.accounts.init(name, fee, &ctx.bumps)?;
+        ctx.accounts.fund_treasury()
     }
 
-    pub fn list(ctx: Context<List>, price: u64){
-        ctx.accounts.create_listing(price,&ctx.bumps)?;
+    pub fn list(ctx: Context<List>, price: u64) -> Result<()> {
+        ctx.accounts.create_listing(price, &ctx.bumps)?;
         ctx.accounts.deposit_nft()
     }
 
+    pub fn list_many<'info>(ctx: Context<'_, '_, 'info, 'info, ListMany<'info>>, prices: Vec<u64>) -> Result<()> {
+        ctx.accounts.list_many(&prices, ctx.remaining_accounts, ctx.program_id)
+    }
 
-    pub fn purchase(ctx: Context<Purchase>) -> Result<()> {
-        
+    pub fn list_delegated(ctx: Context<ListDelegated>, price: u64) -> Result<()> {
+        ctx.accounts.create_listing(price, &ctx.bumps)?;
+        ctx.accounts.delegate_and_freeze()
     }
 
+    pub fn list_english_auction(ctx: Context<List>, reserve: u64, min_increment: u64, duration: i64, extension: i64) -> Result<()> {
+        ctx.accounts.create_english_auction(reserve, min_increment, duration, extension, &ctx.bumps)?;
+        ctx.accounts.deposit_nft()
+    }
 
-}
+    pub fn list_dutch_auction(ctx: Context<List>, start_price: u64, floor_price: u64, duration: i64) -> Result<()> {
+        ctx.accounts.create_dutch_auction(start_price, floor_price, duration, &ctx.bumps)?;
+        ctx.accounts.deposit_nft()
+    }
+
+    pub fn delist(ctx: Context<Delist>) -> Result<()> {
+        ctx.accounts.withdraw_nft()?;
+        ctx.accounts.close_vault()
+    }
+
+    pub fn purchase<'info>(ctx: Context<'_, '_, '_, 'info, Purchase<'info>>) -> Result<()> {
+        ctx.accounts.pay(ctx.remaining_accounts)?;
+        ctx.accounts.mint_rewards()?;
+        ctx.accounts.transfer_nft()?;
+        ctx.accounts.close_vault()
+    }
+
+    pub fn sweep<'info>(ctx: Context<'_, '_, 'info, 'info, Sweep<'info>>, max_count: u8, max_spend: u64) -> Result<()> {
+        ctx.accounts.sweep(max_count, max_spend, ctx.remaining_accounts, ctx.program_id)
+    }
+
+    pub fn set_rewards_rate(ctx: Context<SetRewardsRate>, rewards_rate: u64) -> Result<()> {
+        ctx.accounts.set_rewards_rate(rewards_rate)
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_treasury(amount)
+    }
+
+    pub fn withdraw_treasury_tokens(ctx: Context<WithdrawTreasuryTokens>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_treasury_tokens(amount)
+    }
+
+    pub fn update_fee(ctx: Context<UpdateFee>, fee: u16) -> Result<()> {
+        ctx.accounts.update_fee(fee)
+    }
+
+    pub fn transfer_admin(ctx: Context<TransferAdmin>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.transfer_admin(new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        ctx.accounts.accept_admin()
+    }
+
+    pub fn allow_payment_mint(ctx: Context<AllowPaymentMint>) -> Result<()> {
+        ctx.accounts.allow_payment_mint(&ctx.bumps)
+    }
+
+    pub fn disallow_payment_mint(_ctx: Context<DisallowPaymentMint>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn place_bid(ctx: Context<PlaceBid>, seed: u64, price: u64, mint: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.place_bid(seed, price, mint, &ctx.bumps)?;
+        ctx.accounts.deposit()
+    }
+
+    pub fn cancel_bid(ctx: Context<CancelBid>) -> Result<()> {
+        ctx.accounts.refund_tokens()
+    }
+
+    pub fn accept_bid<'info>(ctx: Context<'_, '_, '_, 'info, AcceptBid<'info>>) -> Result<()> {
+        ctx.accounts.pay(ctx.remaining_accounts)?;
+        ctx.accounts.mint_rewards()?;
+        ctx.accounts.transfer_nft()?;
+        ctx.accounts.close_vault()
+    }
+
+    pub fn bid_on_auction(ctx: Context<BidOnAuction>, amount: u64) -> Result<()> {
+        ctx.accounts.bid(amount)
+    }
+
+    pub fn settle_auction<'info>(ctx: Context<'_, '_, '_, 'info, SettleAuction<'info>>) -> Result<()> {
+        ctx.accounts.pay(ctx.remaining_accounts)?;
+        ctx.accounts.mint_rewards()?;
+        ctx.accounts.transfer_nft()?;
+        ctx.accounts.close_vault()
+    }
+
+    pub fn list_core(ctx: Context<ListCore>, price: u64) -> Result<()> {
+        ctx.accounts.create_listing(price, &ctx.bumps)?;
+        ctx.accounts.approve_delegate()
+    }
+
+    pub fn delist_core(ctx: Context<DelistCore>) -> Result<()> {
+        ctx.accounts.revoke_delegate()
+    }
+
+    pub fn purchase_core(ctx: Context<PurchaseCore>) -> Result<()> {
+        ctx.accounts.pay()?;
+        ctx.accounts.mint_rewards()?;
+        ctx.accounts.transfer_asset()
+    }
 
-#[derive(Accounts)]
-pub struct Initialize {}
+    pub fn list_compressed<'info>(ctx: Context<'_, '_, '_, 'info, ListCompressed<'info>>, price: u64, leaf: LeafArgs, metadata: CompressedMetadata) -> Result<()> {
+        ctx.accounts.create_listing(price, &leaf, &metadata, &ctx.bumps)?;
+        ctx.accounts.deposit_nft(&leaf, ctx.remaining_accounts)
+    }
+
+    pub fn delist_compressed<'info>(ctx: Context<'_, '_, '_, 'info, DelistCompressed<'info>>, leaf: LeafArgs) -> Result<()> {
+        ctx.accounts.withdraw_nft(&leaf, ctx.remaining_accounts)
+    }
+
+    pub fn purchase_compressed<'info>(ctx: Context<'_, '_, '_, 'info, PurchaseCompressed<'info>>, leaf: LeafArgs, metadata: CompressedMetadata) -> Result<()> {
+        let proof = ctx.accounts.pay(&leaf, &metadata, ctx.remaining_accounts)?;
+        ctx.accounts.mint_rewards()?;
+        ctx.accounts.transfer_nft(&leaf, proof)
+    }
+}
@@ -0,0 +1,123 @@
+//! Moving sale proceeds. A listing is priced either in lamports or in an
+//! allowlisted SPL/Token-2022 payment mint; these helpers cover both.
+
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    metadata::MetadataAccount,
+    token_interface::{transfer_checked, TransferChecked},
+};
+
+use crate::error::MarketplaceError;
+
+// Splits `seller_fee_basis_points` of `price` between the verified creators
+// by share, pairing each amount with the account it is paid to: the
+// creator's wallet for a SOL sale, or its ATA for `payment_mint` (given with
+// its token program). `recipients` holds those accounts in metadata order.
+// A creator without an ATA for `payment_mint` is skipped, leaving its share
+// with the maker. So is a SOL royalty to an empty wallet that falls short of
+// the rent-exempt minimum, which would otherwise fail the whole sale.
+pub fn royalties<'info>(
+    metadata: &MetadataAccount,
+    price: u64,
+    payment_mint: Option<(Pubkey, Pubkey)>,
+    recipients: &[AccountInfo<'info>],
+) -> Result<Vec<(AccountInfo<'info>, u64)>> {
+    let verified: Vec<_> = metadata.creators.iter().flatten()
+        .filter(|creator| creator.verified)
+        .map(|creator| (creator.address, creator.share))
+        .collect();
+    split_royalties(metadata.seller_fee_basis_points, &verified, price, payment_mint, recipients)
+}
+
+// `royalties` for creators given as (address, share) rather than read off a
+// metadata account, as a compressed NFT's are.
+pub fn split_royalties<'info>(
+    seller_fee_basis_points: u16,
+    verified: &[(Pubkey, u8)],
+    price: u64,
+    payment_mint: Option<(Pubkey, Pubkey)>,
+    recipients: &[AccountInfo<'info>],
+) -> Result<Vec<(AccountInfo<'info>, u64)>> {
+    let royalty = price as u128 * seller_fee_basis_points as u128 / 10_000;
+    require!(recipients.len() == verified.len(), MarketplaceError::InvalidCreatorAccounts);
+
+    let mut royalties = Vec::with_capacity(verified.len());
+    for (&(address, share), recipient) in verified.iter().zip(recipients) {
+        let expected = match payment_mint {
+            Some((mint, token_program)) => get_associated_token_address_with_program_id(&address, &mint, &token_program),
+            None => address,
+        };
+        require_keys_eq!(expected, recipient.key(), MarketplaceError::InvalidCreatorAccounts);
+
+        let amount = u64::try_from(royalty * share as u128 / 100).map_err(|_| MarketplaceError::Overflow)?;
+        let unpayable = match payment_mint {
+            Some(_) => recipient.data_is_empty(),
+            None => recipient.lamports() == 0 && amount < Rent::get()?.minimum_balance(0),
+        };
+        if unpayable {
+            continue;
+        }
+        royalties.push((recipient.clone(), amount));
+    }
+    Ok(royalties)
+}
+
+// Settles a SOL sale with no royalty metadata to go by: the fee goes to the
+// treasury and the rest to the maker.
+pub fn pay_lamports<'info>(
+    system_program: &AccountInfo<'info>,
+    taker: &AccountInfo<'info>,
+    maker: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    price: u64,
+    fee: u64,
+) -> Result<()> {
+    let proceeds = price.checked_sub(fee).ok_or(MarketplaceError::Overflow)?;
+    transfer_lamports(system_program, taker, maker, proceeds, &[])?;
+    transfer_lamports(system_program, taker, treasury, fee, &[])
+}
+
+pub fn transfer_lamports<'info>(
+    system_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let cpi_accounts = system_program::Transfer {
+        from: from.clone(),
+        to: to.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds);
+    system_program::transfer(cpi_ctx, amount)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_tokens<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let cpi_accounts = TransferChecked {
+        from: from.clone(),
+        mint: mint.clone(),
+        to: to.clone(),
+        authority: authority.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, amount, decimals)
+}
@@ -0,0 +1,46 @@
+//! Minting marketplace rewards. Both sides of a SOL sale earn the
+//! marketplace's `rewards_rate` per SOL. Sales in a payment mint earn nothing,
+//! since a whole token of one mint is worth nothing like a whole SOL.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{mint_to, MintTo};
+
+use crate::state::Marketplace;
+
+// Mints the rewards on a sale of `lamports` to each of `recipients`, the
+// buyer's and the seller's rewards ATAs. Buying from yourself earns nothing,
+// or anyone could wash-trade their own listing for rewards.
+pub fn mint_rewards<'info>(
+    marketplace: &Account<'info, Marketplace>,
+    rewards_mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    buyer: &Pubkey,
+    seller: &Pubkey,
+    recipients: [AccountInfo<'info>; 2],
+    lamports: u64,
+) -> Result<()> {
+    if buyer == seller {
+        return Ok(());
+    }
+    let amount = marketplace.rewards_on(lamports)?;
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let signer_seeds: [&[&[u8]] ;1] = [&[
+        b"marketplace",
+        marketplace.name.as_bytes(),
+        &[marketplace.bump]
+    ]];
+
+    for to in recipients {
+        let cpi_accounts = MintTo {
+            mint: rewards_mint.clone(),
+            to,
+            authority: marketplace.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, &signer_seeds);
+        mint_to(cpi_ctx, amount)?;
+    }
+    Ok(())
+}
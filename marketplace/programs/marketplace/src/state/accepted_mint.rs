@@ -0,0 +1,9 @@
+use anchor_lang::prelude::*;
+
+// Marks `mint` as a payment currency the marketplace admin accepts for listings.
+#[account]
+#[derive(InitSpace)]
+pub struct AcceptedMint {
+    pub mint: Pubkey,
+    pub bump: u8,
+}
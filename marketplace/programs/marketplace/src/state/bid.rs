@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+// An offer to buy any verified member of `collection`, or only `mint` when
+// set. The price sits in escrow until the bid is accepted or cancelled:
+// lamports in this account, tokens in its ATA for `payment_mint`.
+#[account]
+#[derive(InitSpace)]
+pub struct Bid {
+    pub bidder: Pubkey,
+    pub seed: u64,
+    pub collection: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub payment_mint: Option<Pubkey>,
+    pub price: u64,
+    pub bump: u8,
+}
@@ -1,10 +1,78 @@
+use anchor_lang::prelude::*;
 
+use crate::error::MarketplaceError;
 
 #[account]
 #[derive(InitSpace)]
-pub struct List {
+pub struct Listing {
     pub maker: Pubkey,
     pub mint: Pubkey,
+    // The fixed price, an English auction's reserve or a Dutch auction's
+    // starting price.
     pub price: u64,
+    // Priced in lamports when `None`.
+    pub payment_mint: Option<Pubkey>,
+    pub kind: SaleKind,
+    pub standard: AssetStandard,
     pub bump: u8,
 }
+
+// How the listed NFT is held. `mint` is the Core asset's address or the
+// compressed NFT's asset id for the non-legacy standards.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AssetStandard {
+    // Token-metadata NFT escrowed in the listing's vault.
+    Legacy,
+    // Token-metadata NFT left in the maker's ATA, frozen there with the
+    // listing as its delegate.
+    LegacyDelegated,
+    // Stays with the maker, with the listing as its transfer delegate.
+    Core,
+    // Leaf owned by the listing until it sells or is delisted.
+    Compressed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum SaleKind {
+    Fixed,
+    // Goes to the highest bid once `end_ts` passes. Bids are held in the
+    // listing account; a bid within `extension` seconds of the end pushes
+    // the end out to `extension` seconds from then.
+    English {
+        end_ts: i64,
+        min_increment: u64,
+        extension: i64,
+        highest_bidder: Option<Pubkey>,
+        highest_bid: u64,
+    },
+    // Sells to the first taker at a price falling linearly from `price` to
+    // `floor_price` between `start_ts` and `end_ts`.
+    Dutch {
+        start_ts: i64,
+        end_ts: i64,
+        floor_price: u64,
+    },
+}
+
+impl Listing {
+    // What `purchase` charges at `now`; English auctions only sell through
+    // `settle_auction`.
+    pub fn current_price(&self, now: i64) -> Result<u64> {
+        match self.kind {
+            SaleKind::Fixed => Ok(self.price),
+            SaleKind::Dutch { start_ts, end_ts, floor_price } => {
+                if now >= end_ts {
+                    return Ok(floor_price);
+                }
+                let elapsed = (now - start_ts).max(0) as u128;
+                let decay = (self.price - floor_price) as u128 * elapsed / (end_ts - start_ts) as u128;
+                Ok(self.price - decay as u64)
+            }
+            SaleKind::English { .. } => err!(MarketplaceError::NotForSale),
+        }
+    }
+
+    pub fn has_bids(&self) -> bool {
+        matches!(self.kind, SaleKind::English { highest_bidder: Some(_), .. })
+    }
+}
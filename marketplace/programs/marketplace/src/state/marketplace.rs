@@ -1,14 +1,31 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL};
+
+use crate::error::MarketplaceError;
 
 
 #[account]
 #[derive(InitSpace)]
 pub struct Marketplace {
     pub admin: Pubkey,
+    // Proposed by `transfer_admin`; takes over once it signs `accept_admin`.
+    pub pending_admin: Option<Pubkey>,
     pub fee: u16,
+    // Reward tokens (in base units) minted to each side of a SOL sale per SOL.
+    pub rewards_rate: u64,
     pub bump: u8,
     pub treasury_bump: u8,
     pub rewards_bump: u8,
     #[max_len(32)]
     pub name: String,
-}
\ No newline at end of file
+}
+
+impl Marketplace {
+    // `fee` is in basis points of the sale price.
+    pub fn fee_on(&self, price: u64) -> Result<u64> {
+        u64::try_from(price as u128 * self.fee as u128 / 10_000).map_err(|_| error!(MarketplaceError::Overflow))
+    }
+
+    pub fn rewards_on(&self, lamports: u64) -> Result<u64> {
+        u64::try_from(lamports as u128 * self.rewards_rate as u128 / LAMPORTS_PER_SOL as u128).map_err(|_| error!(MarketplaceError::Overflow))
+    }
+}
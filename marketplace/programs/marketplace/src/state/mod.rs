@@ -2,4 +2,10 @@ pub mod marketplace;
 pub use marketplace::*;
 
 pub mod listing;
-pub use listing::*;
\ No newline at end of file
+pub use listing::*;
+
+pub mod accepted_mint;
+pub use accepted_mint::*;
+
+pub mod bid;
+pub use bid::*;
@@ -1,16 +1,1119 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Marketplace } from "../target/types/marketplace";
+import * as spl from "@solana/spl-token";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { assert } from "chai";
+import { createUmi } from "@metaplex-foundation/umi-bundle-defaults";
+import { createSignerFromKeypair, generateSigner, none, percentAmount, publicKey, PublicKey as UmiPublicKey, signerIdentity, some } from "@metaplex-foundation/umi";
+import { create as createCoreAsset, createCollection as createCoreCollection, fetchAsset, mplCore, MPL_CORE_PROGRAM_ID } from "@metaplex-foundation/mpl-core";
+import {
+  createTree,
+  findLeafAssetIdPda,
+  findTreeConfigPda,
+  getMerkleProof,
+  getMerkleRoot,
+  hashLeaf,
+  hashMetadataCreators,
+  hashMetadataData,
+  mintToCollectionV1,
+  mplBubblegum,
+  MPL_BUBBLEGUM_PROGRAM_ID,
+  SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+  SPL_NOOP_PROGRAM_ID,
+  TokenProgramVersion,
+  TokenStandard,
+} from "@metaplex-foundation/mpl-bubblegum";
+import { createNft, findMetadataPda, mplTokenMetadata, verifyCollectionV1, verifyCreatorV1 } from "@metaplex-foundation/mpl-token-metadata";
 
 describe("marketplace", () => {
-  // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
-
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
   const program = anchor.workspace.Marketplace as Program<Marketplace>;
+  const umi = createUmi(provider.connection.rpcEndpoint).use(mplTokenMetadata()).use(mplCore()).use(mplBubblegum());
+
+  const NAME = "turbin3";
+  const FEE_BPS = 250;
+  const PRICE = new anchor.BN(LAMPORTS_PER_SOL);
+
+  const [marketplace] = PublicKey.findProgramAddressSync(
+    [Buffer.from("marketplace"), Buffer.from(NAME)],
+    program.programId
+  );
+  const [treasury] = PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury"), marketplace.toBuffer()],
+    program.programId
+  );
+  const [rewardsMint] = PublicKey.findProgramAddressSync(
+    [Buffer.from("rewards"), marketplace.toBuffer()],
+    program.programId
+  );
+
+  let maker: Keypair;
+  let taker: Keypair;
+  let collection: PublicKey;
+
+  before(async () => {
+    maker = Keypair.generate();
+    taker = Keypair.generate();
+    await fundAccount(maker.publicKey, 10 * LAMPORTS_PER_SOL);
+    await fundAccount(taker.publicKey, 10 * LAMPORTS_PER_SOL);
+
+    // The maker owns the collection and verifies the items it mints.
+    umi.use(signerIdentity(createSignerFromKeypair(umi, umi.eddsa.createKeypairFromSecretKey(maker.secretKey))));
+    const collectionMint = generateSigner(umi);
+    await createNft(umi, {
+      mint: collectionMint,
+      name: "Marketplace Collection",
+      uri: "https://example.com/collection.json",
+      sellerFeeBasisPoints: percentAmount(0),
+      isCollection: true,
+    }).sendAndConfirm(umi);
+    collection = new PublicKey(collectionMint.publicKey);
+
+    await program.methods
+      .initialize(NAME, FEE_BPS)
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, treasury, tokenProgram: spl.TOKEN_PROGRAM_ID })
+      .rpc();
+  });
+
+  async function fundAccount(pubkey: PublicKey, amount: number) {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(pubkey, amount),
+      "confirmed"
+    );
+  }
+
+  // Mints a verified member of the collection to the maker. Unless
+  // `royalties` names another creator, the maker is its sole creator.
+  async function mintNft(verified = true, royalties?: { creator: Keypair; percent: number }): Promise<PublicKey> {
+    const nft = generateSigner(umi);
+    await createNft(umi, {
+      mint: nft,
+      name: "Marketplace Item",
+      uri: "https://example.com/item.json",
+      sellerFeeBasisPoints: percentAmount(royalties?.percent ?? 0),
+      creators: royalties && [{ address: publicKey(royalties.creator.publicKey.toBase58()), verified: false, share: 100 }],
+      collection: some({ key: publicKey(collection.toBase58()), verified: false }),
+    }).sendAndConfirm(umi);
+    if (royalties) {
+      const creator = createSignerFromKeypair(umi, umi.eddsa.createKeypairFromSecretKey(royalties.creator.secretKey));
+      await verifyCreatorV1(umi, {
+        metadata: findMetadataPda(umi, { mint: nft.publicKey }),
+        authority: creator,
+      }).sendAndConfirm(umi);
+    }
+    if (verified) {
+      await verifyCollectionV1(umi, {
+        metadata: findMetadataPda(umi, { mint: nft.publicKey }),
+        collectionMint: publicKey(collection.toBase58()),
+      }).sendAndConfirm(umi);
+    }
+    return new PublicKey(nft.publicKey);
+  }
+
+  function deriveListing(mint: PublicKey) {
+    const [listing] = PublicKey.findProgramAddressSync([marketplace.toBuffer(), mint.toBuffer()], program.programId);
+    return { listing, vault: spl.getAssociatedTokenAddressSync(mint, listing, true) };
+  }
+
+  function deriveAcceptedMint(paymentMint: PublicKey) {
+    const [acceptedMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("accepted"), marketplace.toBuffer(), paymentMint.toBuffer()],
+      program.programId
+    );
+    return acceptedMint;
+  }
+
+  // Accounts a purchase of a listing priced in `paymentMint` pays through.
+  function paymentAccounts(paymentMint: PublicKey) {
+    return {
+      paymentMint,
+      takerPaymentAta: spl.getAssociatedTokenAddressSync(paymentMint, taker.publicKey),
+      makerPaymentAta: spl.getAssociatedTokenAddressSync(paymentMint, maker.publicKey),
+      treasuryPaymentAta: spl.getAssociatedTokenAddressSync(paymentMint, treasury, true),
+      paymentTokenProgram: spl.TOKEN_PROGRAM_ID,
+    };
+  }
+
+  function purchase(mint: PublicKey, creators: PublicKey[] = [maker.publicKey], paymentMint?: PublicKey) {
+    const { listing, vault } = deriveListing(mint);
+    return program.methods
+      .purchase()
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        marketplace,
+        makerMint: mint,
+        listing,
+        vault,
+        treasury,
+        rewardsMint,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+        ...(paymentMint && paymentAccounts(paymentMint)),
+      })
+      .remainingAccounts(creators.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })))
+      .signers([taker])
+      .rpc();
+  }
+
+  async function list(mint: PublicKey, paymentMint?: PublicKey) {
+    const { listing, vault } = deriveListing(mint);
+    await program.methods
+      .list(PRICE)
+      .accountsPartial({
+        maker: maker.publicKey,
+        marketplace,
+        makerMint: mint,
+        makerAta: spl.getAssociatedTokenAddressSync(mint, maker.publicKey),
+        collectionMint: collection,
+        listing,
+        vault,
+        acceptedMint: paymentMint ? deriveAcceptedMint(paymentMint) : null,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+    return { listing, vault };
+  }
+
+  it("stores the name and fee on initialize", async () => {
+    const account = await program.account.marketplace.fetch(marketplace);
+    assert.equal(account.name, NAME);
+    assert.equal(account.fee, FEE_BPS);
+    assert.isTrue(account.admin.equals(provider.wallet.publicKey));
+  });
+
+  it("moves a listed NFT into the listing vault", async () => {
+    const mint = await mintNft();
+    const { listing, vault } = await list(mint);
+
+    const account = await program.account.listing.fetch(listing);
+    assert.isTrue(account.maker.equals(maker.publicKey));
+    assert.equal(account.price.toString(), PRICE.toString());
+    assert.equal((await spl.getAccount(provider.connection, vault)).amount.toString(), "1");
+  });
+
+  it("refuses to list an NFT outside the verified collection", async () => {
+    const mint = await mintNft(false);
+
+    try {
+      await list(mint);
+      assert.fail("list should have failed");
+    } catch (err) {
+      assert.include(err.message, "CollectionNotVerified");
+    }
+  });
+
+  it("returns the NFT to the maker on delist", async () => {
+    const mint = await mintNft();
+    const { listing, vault } = await list(mint);
+
+    await program.methods
+      .delist()
+      .accountsPartial({ maker: maker.publicKey, marketplace, makerMint: mint, listing, vault, tokenProgram: spl.TOKEN_PROGRAM_ID })
+      .signers([maker])
+      .rpc();
+
+    const makerAta = spl.getAssociatedTokenAddressSync(mint, maker.publicKey);
+    assert.equal((await spl.getAccount(provider.connection, makerAta)).amount.toString(), "1");
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+    assert.isNull(await provider.connection.getAccountInfo(vault));
+  });
+
+  it("pays the maker minus the fee and hands the NFT to the buyer on purchase", async () => {
+    const mint = await mintNft();
+    const { listing } = await list(mint);
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+    const makerBefore = await provider.connection.getBalance(maker.publicKey);
+
+    await purchase(mint);
+
+    const fee = (PRICE.toNumber() * FEE_BPS) / 10_000;
+    assert.equal((await provider.connection.getBalance(treasury)) - treasuryBefore, fee);
+    // The maker also gets the listing and vault rent back.
+    assert.isAtLeast((await provider.connection.getBalance(maker.publicKey)) - makerBefore, PRICE.toNumber() - fee);
+
+    const takerAta = spl.getAssociatedTokenAddressSync(mint, taker.publicKey);
+    assert.equal((await spl.getAccount(provider.connection, takerAta)).amount.toString(), "1");
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+  });
+
+  it("pays verified creators their royalty out of the maker's proceeds", async () => {
+    const artist = Keypair.generate();
+    await fundAccount(artist.publicKey, LAMPORTS_PER_SOL);
+    const mint = await mintNft(true, { creator: artist, percent: 5 });
+    await list(mint);
+
+    try {
+      await purchase(mint, []);
+      assert.fail("purchase without the creator wallet should have failed");
+    } catch (err) {
+      assert.include(err.message, "InvalidCreatorAccounts");
+    }
+
+    const artistBefore = await provider.connection.getBalance(artist.publicKey);
+    await purchase(mint, [artist.publicKey]);
+
+    const royalty = (PRICE.toNumber() * 500) / 10_000;
+    assert.equal((await provider.connection.getBalance(artist.publicKey)) - artistBefore, royalty);
+  });
+
+  it("leaves a SOL royalty below rent with the maker when the creator's wallet is empty", async () => {
+    // 0.05% of the price is well under what it takes to open an empty wallet.
+    const artist = Keypair.generate();
+    const mint = await mintNft(true, { creator: artist, percent: 0.05 });
+    await list(mint);
+
+    await purchase(mint, [artist.publicKey]);
+
+    assert.equal(await provider.connection.getBalance(artist.publicKey), 0);
+    const takerAta = spl.getAssociatedTokenAddressSync(mint, taker.publicKey);
+    assert.equal((await spl.getAccount(provider.connection, takerAta)).amount.toString(), "1");
+  });
+
+  it("sells for an allowlisted SPL token", async () => {
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const usdc = await spl.createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const takerUsdc = await spl.createAssociatedTokenAccount(provider.connection, payer, usdc, taker.publicKey);
+    await spl.mintTo(provider.connection, payer, usdc, takerUsdc, payer, PRICE.toNumber());
+
+    const mint = await mintNft();
+    try {
+      await list(mint, usdc);
+      assert.fail("listing in a mint that isn't allowlisted should have failed");
+    } catch (err) {
+      assert.include(err.message, "AccountNotInitialized");
+    }
+
+    await program.methods
+      .allowPaymentMint()
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, paymentMint: usdc, acceptedMint: deriveAcceptedMint(usdc) })
+      .rpc();
+    const { listing } = await list(mint, usdc);
+    assert.isTrue((await program.account.listing.fetch(listing)).paymentMint.equals(usdc));
+
+    try {
+      await purchase(mint);
+      assert.fail("paying in SOL for a token listing should have failed");
+    } catch (err) {
+      assert.include(err.message, "PaymentMintMismatch");
+    }
+    await purchase(mint, [maker.publicKey], usdc);
+
+    const { makerPaymentAta, treasuryPaymentAta } = paymentAccounts(usdc);
+    const fee = (PRICE.toNumber() * FEE_BPS) / 10_000;
+    assert.equal(Number((await spl.getAccount(provider.connection, makerPaymentAta)).amount), PRICE.toNumber() - fee);
+    assert.equal(Number((await spl.getAccount(provider.connection, treasuryPaymentAta)).amount), fee);
+
+    try {
+      await program.methods
+        .withdrawTreasuryTokens(new anchor.BN(fee + 1))
+        .accountsPartial({ admin: provider.wallet.publicKey, marketplace, treasury, paymentMint: usdc, tokenProgram: spl.TOKEN_PROGRAM_ID })
+        .rpc();
+      assert.fail("withdrawTreasuryTokens above the balance should have failed");
+    } catch (err) {
+      assert.include(err.message, "InsufficientTreasury");
+    }
+    await program.methods
+      .withdrawTreasuryTokens(new anchor.BN(fee))
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, treasury, paymentMint: usdc, tokenProgram: spl.TOKEN_PROGRAM_ID })
+      .rpc();
+    const adminUsdc = spl.getAssociatedTokenAddressSync(usdc, provider.wallet.publicKey);
+    assert.equal(Number((await spl.getAccount(provider.connection, adminUsdc)).amount), fee);
+    assert.equal(Number((await spl.getAccount(provider.connection, treasuryPaymentAta)).amount), 0);
+
+    await program.methods
+      .disallowPaymentMint()
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, acceptedMint: deriveAcceptedMint(usdc) })
+      .rpc();
+    assert.isNull(await provider.connection.getAccountInfo(deriveAcceptedMint(usdc)));
+  });
+
+  it("leaves a token royalty with the maker when the creator has no ATA for the payment mint", async () => {
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const usdc = await spl.createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const takerUsdc = await spl.createAssociatedTokenAccount(provider.connection, payer, usdc, taker.publicKey);
+    await spl.mintTo(provider.connection, payer, usdc, takerUsdc, payer, 2 * PRICE.toNumber());
+    await program.methods
+      .allowPaymentMint()
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, paymentMint: usdc, acceptedMint: deriveAcceptedMint(usdc) })
+      .rpc();
+
+    const artist = Keypair.generate();
+    await fundAccount(artist.publicKey, LAMPORTS_PER_SOL);
+    const artistUsdc = spl.getAssociatedTokenAddressSync(usdc, artist.publicKey);
+    const { makerPaymentAta } = paymentAccounts(usdc);
+    const fee = (PRICE.toNumber() * FEE_BPS) / 10_000;
+    const royalty = (PRICE.toNumber() * 500) / 10_000;
+
+    const unpaid = await mintNft(true, { creator: artist, percent: 5 });
+    await list(unpaid, usdc);
+    await purchase(unpaid, [artistUsdc], usdc);
+    assert.equal(Number((await spl.getAccount(provider.connection, makerPaymentAta)).amount), PRICE.toNumber() - fee);
+
+    await spl.createAssociatedTokenAccount(provider.connection, payer, usdc, artist.publicKey);
+    const paid = await mintNft(true, { creator: artist, percent: 5 });
+    await list(paid, usdc);
+    await purchase(paid, [artistUsdc], usdc);
+    assert.equal(Number((await spl.getAccount(provider.connection, artistUsdc)).amount), royalty);
+    assert.equal(Number((await spl.getAccount(provider.connection, makerPaymentAta)).amount), 2 * (PRICE.toNumber() - fee) - royalty);
+
+    await program.methods
+      .disallowPaymentMint()
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, acceptedMint: deriveAcceptedMint(usdc) })
+      .rpc();
+  });
+
+  function deriveBid(seed: number) {
+    const [bid] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bid"), marketplace.toBuffer(), taker.publicKey.toBuffer(), new anchor.BN(seed).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    return bid;
+  }
+
+  async function placeBid(seed: number, mint: PublicKey | null = null) {
+    await program.methods
+      .placeBid(new anchor.BN(seed), PRICE, mint)
+      .accountsPartial({ bidder: taker.publicKey, marketplace, collectionMint: collection, bid: deriveBid(seed) })
+      .signers([taker])
+      .rpc();
+    return deriveBid(seed);
+  }
+
+  function acceptBid(seed: number, mint: PublicKey) {
+    return program.methods
+      .acceptBid()
+      .accountsPartial({
+        seller: maker.publicKey,
+        bidder: taker.publicKey,
+        marketplace,
+        sellerMint: mint,
+        sellerAta: spl.getAssociatedTokenAddressSync(mint, maker.publicKey),
+        bid: deriveBid(seed),
+        treasury,
+        rewardsMint,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([{ pubkey: maker.publicKey, isWritable: true, isSigner: false }])
+      .signers([maker])
+      .rpc();
+  }
+
+  it("fills a collection bid with any verified member", async () => {
+    const bid = await placeBid(1);
+    const mint = await mintNft();
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+
+    await acceptBid(1, mint);
+
+    const fee = (PRICE.toNumber() * FEE_BPS) / 10_000;
+    assert.equal((await provider.connection.getBalance(treasury)) - treasuryBefore, fee);
+    const takerAta = spl.getAssociatedTokenAddressSync(mint, taker.publicKey);
+    assert.equal((await spl.getAccount(provider.connection, takerAta)).amount.toString(), "1");
+    assert.isNull(await provider.connection.getAccountInfo(bid));
+  });
+
+  it("only fills a mint bid with that mint, and refunds it on cancel", async () => {
+    const wanted = await mintNft();
+    const other = await mintNft();
+    const bid = await placeBid(2, wanted);
+
+    try {
+      await acceptBid(2, other);
+      assert.fail("acceptBid with another mint should have failed");
+    } catch (err) {
+      assert.include(err.message, "BidMintMismatch");
+    }
+
+    const takerBefore = await provider.connection.getBalance(taker.publicKey);
+    await program.methods
+      .cancelBid()
+      .accountsPartial({ bidder: taker.publicKey, marketplace, bid })
+      .signers([taker])
+      .rpc();
+    assert.isAtLeast((await provider.connection.getBalance(taker.publicKey)) - takerBefore, PRICE.toNumber());
+    assert.isNull(await provider.connection.getAccountInfo(bid));
+  });
+
+  function listAuction(mint: PublicKey, method: any) {
+    const { listing, vault } = deriveListing(mint);
+    return method
+      .accountsPartial({
+        maker: maker.publicKey,
+        marketplace,
+        makerMint: mint,
+        makerAta: spl.getAssociatedTokenAddressSync(mint, maker.publicKey),
+        collectionMint: collection,
+        listing,
+        vault,
+        acceptedMint: null,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+  }
+
+  function bidOnAuction(mint: PublicKey, bidder: Keypair, amount: number, previousBidder: PublicKey | null = null) {
+    return program.methods
+      .bidOnAuction(new anchor.BN(amount))
+      .accountsPartial({ bidder: bidder.publicKey, marketplace, listing: deriveListing(mint).listing, previousBidder })
+      .signers([bidder])
+      .rpc();
+  }
+
+  it("sells a Dutch auction below its start price as time passes", async () => {
+    const mint = await mintNft();
+    const floor = PRICE.toNumber() / 2;
+    await listAuction(mint, program.methods.listDutchAuction(PRICE, new anchor.BN(floor), new anchor.BN(4)));
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+
+    const makerBefore = await provider.connection.getBalance(maker.publicKey);
+    await purchase(mint);
+
+    const gain = (await provider.connection.getBalance(maker.publicKey)) - makerBefore;
+    assert.isBelow(gain, PRICE.toNumber());
+    assert.isAtLeast(gain, floor - (floor * FEE_BPS) / 10_000);
+  });
+
+  it("runs an English auction to settlement, refunding the outbid bidder", async () => {
+    const rival = Keypair.generate();
+    await fundAccount(rival.publicKey, 10 * LAMPORTS_PER_SOL);
+    const increment = LAMPORTS_PER_SOL / 10;
+    const mint = await mintNft();
+    const { listing, vault } = deriveListing(mint);
+    await listAuction(mint, program.methods.listEnglishAuction(PRICE, new anchor.BN(increment), new anchor.BN(4), new anchor.BN(0)));
+
+    try {
+      await purchase(mint);
+      assert.fail("purchase of an English auction should have failed");
+    } catch (err) {
+      assert.include(err.message, "NotForSale");
+    }
+    try {
+      await bidOnAuction(mint, taker, PRICE.toNumber() - 1);
+      assert.fail("a bid below the reserve should have failed");
+    } catch (err) {
+      assert.include(err.message, "BidTooLow");
+    }
+
+    await bidOnAuction(mint, taker, PRICE.toNumber());
+    const takerBefore = await provider.connection.getBalance(taker.publicKey);
+    await bidOnAuction(mint, rival, PRICE.toNumber() + increment, taker.publicKey);
+    assert.equal((await provider.connection.getBalance(taker.publicKey)) - takerBefore, PRICE.toNumber());
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+    await program.methods
+      .settleAuction()
+      .accountsPartial({
+        settler: provider.wallet.publicKey,
+        maker: maker.publicKey,
+        winner: rival.publicKey,
+        marketplace,
+        makerMint: mint,
+        listing,
+        vault,
+        treasury,
+        rewardsMint,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([{ pubkey: maker.publicKey, isWritable: true, isSigner: false }])
+      .rpc();
+
+    const rivalAta = spl.getAssociatedTokenAddressSync(mint, rival.publicKey);
+    assert.equal((await spl.getAccount(provider.connection, rivalAta)).amount.toString(), "1");
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+  });
+
+  it("extends an English auction on a late bid", async () => {
+    const mint = await mintNft();
+    const { listing } = deriveListing(mint);
+    await listAuction(mint, program.methods.listEnglishAuction(PRICE, new anchor.BN(1), new anchor.BN(2), new anchor.BN(60)));
+    const endBefore = (await program.account.listing.fetch(listing)).kind.english.endTs.toNumber();
+
+    await bidOnAuction(mint, taker, PRICE.toNumber());
+
+    const endAfter = (await program.account.listing.fetch(listing)).kind.english.endTs.toNumber();
+    assert.isAtLeast(endAfter, endBefore + 50);
+  });
+
+  it("sells a Core asset through the listing's transfer delegate", async () => {
+    const coreCollection = generateSigner(umi);
+    await createCoreCollection(umi, { collection: coreCollection, name: "Core Collection", uri: "https://example.com/core.json" }).sendAndConfirm(umi);
+    const coreAsset = generateSigner(umi);
+    await createCoreAsset(umi, {
+      asset: coreAsset,
+      collection: { publicKey: coreCollection.publicKey },
+      name: "Core Item",
+      uri: "https://example.com/core-item.json",
+    }).sendAndConfirm(umi);
+
+    const asset = new PublicKey(coreAsset.publicKey);
+    const collection = new PublicKey(coreCollection.publicKey);
+    const { listing } = deriveListing(asset);
+    await program.methods
+      .listCore(PRICE)
+      .accountsPartial({ maker: maker.publicKey, marketplace, asset, collection, listing })
+      .signers([maker])
+      .rpc();
+    // The asset stays with the maker until it sells.
+    assert.equal((await fetchAsset(umi, coreAsset.publicKey)).owner.toString(), maker.publicKey.toBase58());
+
+    const makerBefore = await provider.connection.getBalance(maker.publicKey);
+    await program.methods
+      .purchaseCore()
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        marketplace,
+        asset,
+        collection,
+        listing,
+        treasury,
+        rewardsMint,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([taker])
+      .rpc();
+
+    const fee = (PRICE.toNumber() * FEE_BPS) / 10_000;
+    assert.isAtLeast((await provider.connection.getBalance(maker.publicKey)) - makerBefore, PRICE.toNumber() - fee);
+    assert.equal((await fetchAsset(umi, coreAsset.publicKey)).owner.toString(), taker.publicKey.toBase58());
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+  });
+
+  it("lists a Core asset without a collection against Core's program id", async () => {
+    const coreAsset = generateSigner(umi);
+    await createCoreAsset(umi, { asset: coreAsset, name: "Loose Core Item", uri: "https://example.com/loose.json" }).sendAndConfirm(umi);
+
+    const asset = new PublicKey(coreAsset.publicKey);
+    const placeholder = new PublicKey(MPL_CORE_PROGRAM_ID);
+    const { listing } = deriveListing(asset);
+    await program.methods
+      .listCore(PRICE)
+      .accountsPartial({ maker: maker.publicKey, marketplace, asset, collection: placeholder, listing })
+      .signers([maker])
+      .rpc();
+    assert.isNotNull(await provider.connection.getAccountInfo(listing));
+
+    await program.methods
+      .delistCore()
+      .accountsPartial({ maker: maker.publicKey, marketplace, asset, collection: placeholder, listing })
+      .signers([maker])
+      .rpc();
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+  });
+
+  it("lists, delists and sells a compressed NFT from the verified collection", async () => {
+    const MAX_DEPTH = 3;
+    const tree = generateSigner(umi);
+    await (await createTree(umi, { merkleTree: tree, maxDepth: MAX_DEPTH, maxBufferSize: 8 })).sendAndConfirm(umi);
+
+    // The maker is the NFT's only creator, so its royalty comes back to it.
+    const metadata = {
+      name: "Compressed Item",
+      symbol: "",
+      uri: "https://example.com/compressed.json",
+      sellerFeeBasisPoints: 500,
+      primarySaleHappened: false,
+      isMutable: true,
+      editionNonce: none<number>(),
+      tokenStandard: some(TokenStandard.NonFungible),
+      collection: some({ key: publicKey(collection.toBase58()), verified: false }),
+      uses: none(),
+      tokenProgramVersion: TokenProgramVersion.Original,
+      creators: [{ address: publicKey(maker.publicKey.toBase58()), verified: true, share: 100 }],
+    };
+    await mintToCollectionV1(umi, {
+      leafOwner: publicKey(maker.publicKey.toBase58()),
+      merkleTree: tree.publicKey,
+      collectionMint: publicKey(collection.toBase58()),
+      metadata,
+    }).sendAndConfirm(umi);
+
+    // Minting into the collection verifies it.
+    const minted = { ...metadata, collection: some({ key: publicKey(collection.toBase58()), verified: true }) };
+    const args = {
+      name: minted.name,
+      symbol: minted.symbol,
+      uri: minted.uri,
+      sellerFeeBasisPoints: minted.sellerFeeBasisPoints,
+      primarySaleHappened: minted.primarySaleHappened,
+      isMutable: minted.isMutable,
+      editionNonce: null,
+      tokenStandard: TokenStandard.NonFungible,
+      collection: { verified: true, key: collection },
+      uses: null,
+      tokenProgramVersion: TokenProgramVersion.Original,
+      creators: [{ address: maker.publicKey, verified: true, share: 100 }],
+    };
+
+    const [assetId] = findLeafAssetIdPda(umi, { merkleTree: tree.publicKey, leafIndex: 0 });
+    const { listing } = deriveListing(new PublicKey(assetId));
+    const merkleTree = new PublicKey(tree.publicKey);
+    const programs = {
+      treeConfig: new PublicKey(findTreeConfigPda(umi, { merkleTree: tree.publicKey })[0]),
+      bubblegumProgram: new PublicKey(MPL_BUBBLEGUM_PROGRAM_ID),
+      logWrapper: new PublicKey(SPL_NOOP_PROGRAM_ID),
+      compressionProgram: new PublicKey(SPL_ACCOUNT_COMPRESSION_PROGRAM_ID),
+    };
+
+    // Every leaf but the first is empty, so the tree can be rebuilt here to
+    // get the root and proof for the leaf's current owner.
+    const empty = publicKey(new Uint8Array(32));
+    const leafFor = (owner: PublicKey) => {
+      const leaves: UmiPublicKey[] = Array(2 ** MAX_DEPTH).fill(empty);
+      leaves[0] = publicKey(
+        hashLeaf(umi, { merkleTree: tree.publicKey, owner: publicKey(owner.toBase58()), leafIndex: 0, metadata: minted })
+      );
+      const leaf = {
+        root: Array.from(new PublicKey(getMerkleRoot(leaves, MAX_DEPTH)).toBytes()),
+        dataHash: Array.from(hashMetadataData(minted)),
+        creatorHash: Array.from(hashMetadataCreators(minted.creators)),
+        nonce: new anchor.BN(0),
+        index: 0,
+      };
+      const proof = getMerkleProof(leaves, MAX_DEPTH, leaves[0]).map((node) => ({
+        pubkey: new PublicKey(node),
+        isWritable: false,
+        isSigner: false,
+      }));
+      return { leaf, proof };
+    };
+    const listCompressed = (collectionMint: PublicKey) => {
+      const { leaf, proof } = leafFor(maker.publicKey);
+      return program.methods
+        .listCompressed(PRICE, leaf, args)
+        .accountsPartial({ maker: maker.publicKey, marketplace, collectionMint, merkleTree, listing, ...programs })
+        .remainingAccounts(proof)
+        .signers([maker])
+        .rpc();
+    };
+
+    try {
+      await listCompressed(await mintNft());
+      assert.fail("listing against another collection should have failed");
+    } catch (err) {
+      assert.include(err.message, "CollectionNotVerified");
+    }
+
+    await listCompressed(collection);
+    assert.isNotNull(await provider.connection.getAccountInfo(listing));
+    {
+      const { leaf, proof } = leafFor(listing);
+      await program.methods
+        .delistCompressed(leaf)
+        .accountsPartial({ maker: maker.publicKey, marketplace, merkleTree, listing, ...programs })
+        .remainingAccounts(proof)
+        .signers([maker])
+        .rpc();
+      assert.isNull(await provider.connection.getAccountInfo(listing));
+    }
+
+    await listCompressed(collection);
+    const makerBefore = await provider.connection.getBalance(maker.publicKey);
+    const { leaf, proof } = leafFor(listing);
+    await program.methods
+      .purchaseCompressed(leaf, args)
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        marketplace,
+        merkleTree,
+        listing,
+        treasury,
+        rewardsMint,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+        ...programs,
+      })
+      .remainingAccounts([{ pubkey: maker.publicKey, isWritable: true, isSigner: false }, ...proof])
+      .signers([taker])
+      .rpc();
+
+    const fee = (PRICE.toNumber() * FEE_BPS) / 10_000;
+    assert.isAtLeast((await provider.connection.getBalance(maker.publicKey)) - makerBefore, PRICE.toNumber() - fee);
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+  });
+
+  async function listDelegated(mint: PublicKey) {
+    const { listing } = deriveListing(mint);
+    await program.methods
+      .listDelegated(PRICE)
+      .accountsPartial({
+        maker: maker.publicKey,
+        marketplace,
+        makerMint: mint,
+        makerAta: spl.getAssociatedTokenAddressSync(mint, maker.publicKey),
+        collectionMint: collection,
+        listing,
+        acceptedMint: null,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+    return listing;
+  }
+
+  it("keeps a delegated listing frozen in the maker's wallet until it sells", async () => {
+    const mint = await mintNft();
+    const listing = await listDelegated(mint);
+    const makerAta = spl.getAssociatedTokenAddressSync(mint, maker.publicKey);
+
+    const frozen = await spl.getAccount(provider.connection, makerAta);
+    assert.equal(frozen.amount.toString(), "1");
+    assert.isTrue(frozen.isFrozen);
+    assert.isTrue(frozen.delegate.equals(listing));
+
+    await program.methods
+      .purchase()
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        marketplace,
+        makerMint: mint,
+        listing,
+        vault: null,
+        makerAta,
+        treasury,
+        rewardsMint,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([{ pubkey: maker.publicKey, isWritable: true, isSigner: false }])
+      .signers([taker])
+      .rpc();
+
+    const takerAta = spl.getAssociatedTokenAddressSync(mint, taker.publicKey);
+    assert.equal((await spl.getAccount(provider.connection, takerAta)).amount.toString(), "1");
+    assert.equal((await spl.getAccount(provider.connection, makerAta)).amount.toString(), "0");
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+  });
+
+  it("thaws and revokes a delegated listing on delist", async () => {
+    const mint = await mintNft();
+    const listing = await listDelegated(mint);
+
+    await program.methods
+      .delist()
+      .accountsPartial({ maker: maker.publicKey, marketplace, makerMint: mint, listing, vault: null, tokenProgram: spl.TOKEN_PROGRAM_ID })
+      .signers([maker])
+      .rpc();
+
+    const makerAta = await spl.getAccount(provider.connection, spl.getAssociatedTokenAddressSync(mint, maker.publicKey));
+    assert.isFalse(makerAta.isFrozen);
+    assert.isNull(makerAta.delegate);
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+  });
+
+  const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+  function metadataPdas(mint: PublicKey) {
+    const [metadata] = PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    );
+    const [masterEdition] = PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer(), Buffer.from("edition")],
+      TOKEN_METADATA_PROGRAM_ID
+    );
+    return { metadata, masterEdition };
+  }
+
+  function writable(pubkey: PublicKey) {
+    return { pubkey, isWritable: true, isSigner: false };
+  }
+
+  function readonly(pubkey: PublicKey) {
+    return { pubkey, isWritable: false, isSigner: false };
+  }
+
+  async function listMany(mints: PublicKey[], prices: number[]) {
+    const remainingAccounts = mints.flatMap((mint) => {
+      const { listing, vault } = deriveListing(mint);
+      const { metadata, masterEdition } = metadataPdas(mint);
+      return [
+        readonly(mint),
+        writable(spl.getAssociatedTokenAddressSync(mint, maker.publicKey)),
+        writable(vault),
+        writable(listing),
+        readonly(metadata),
+        readonly(masterEdition),
+      ];
+    });
+    await program.methods
+      .listMany(prices.map((price) => new anchor.BN(price)))
+      .accountsPartial({ maker: maker.publicKey, marketplace, collectionMint: collection, tokenProgram: spl.TOKEN_PROGRAM_ID })
+      .remainingAccounts(remainingAccounts)
+      .signers([maker])
+      .rpc();
+  }
+
+  it("lists a batch of NFTs in one transaction", async () => {
+    const mints = [await mintNft(), await mintNft()];
+    // Lamports sent to a listing address ahead of time don't block it.
+    const prefunded = deriveListing(mints[0]).listing;
+    await fundAccount(prefunded, LAMPORTS_PER_SOL / 1000);
+    await listMany(mints, [PRICE.toNumber(), 2 * PRICE.toNumber()]);
+
+    for (const [i, mint] of mints.entries()) {
+      const { listing, vault } = deriveListing(mint);
+      assert.equal((await program.account.listing.fetch(listing)).price.toNumber(), (i + 1) * PRICE.toNumber());
+      assert.equal((await spl.getAccount(provider.connection, vault)).amount.toString(), "1");
+    }
+  });
+
+  it("sweeps the cheapest listings within the count and spend limits", async () => {
+    const step = LAMPORTS_PER_SOL / 10;
+    const mints = [await mintNft(), await mintNft(), await mintNft()];
+    await listMany(mints, [3 * step, step, 2 * step]);
+    // An English auction can't be swept and is passed over.
+    const auctioned = await mintNft();
+    await listAuction(auctioned, program.methods.listEnglishAuction(new anchor.BN(step / 2), new anchor.BN(1), new anchor.BN(60), new anchor.BN(0)));
+
+    // One whole reward token (6 decimals) per SOL of sale price.
+    const RATE = 1_000_000;
+    await program.methods
+      .setRewardsRate(new anchor.BN(RATE))
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+    const takerRewards = spl.getAssociatedTokenAddressSync(rewardsMint, taker.publicKey);
+    const rewardsBefore = Number((await spl.getAccount(provider.connection, takerRewards)).amount);
+
+    // Each candidate's accounts, followed by its verified creator (the maker).
+    const remainingAccounts = [auctioned, ...mints].flatMap((mint) => {
+      const { listing, vault } = deriveListing(mint);
+      return [
+        writable(listing),
+        writable(maker.publicKey),
+        readonly(mint),
+        writable(vault),
+        writable(spl.getAssociatedTokenAddressSync(mint, taker.publicKey)),
+        readonly(metadataPdas(mint).metadata),
+        writable(spl.getAssociatedTokenAddressSync(rewardsMint, maker.publicKey)),
+        writable(maker.publicKey),
+      ];
+    });
+    await program.methods
+      .sweep(2, new anchor.BN(3 * step))
+      .accountsPartial({ taker: taker.publicKey, marketplace, collectionMint: collection, treasury, rewardsMint, tokenProgram: spl.TOKEN_PROGRAM_ID })
+      .remainingAccounts(remainingAccounts)
+      .signers([taker])
+      .rpc();
+
+    for (const mint of [mints[1], mints[2]]) {
+      const takerAta = spl.getAssociatedTokenAddressSync(mint, taker.publicKey);
+      assert.equal((await spl.getAccount(provider.connection, takerAta)).amount.toString(), "1");
+      assert.isNull(await provider.connection.getAccountInfo(deriveListing(mint).listing));
+    }
+    assert.isNotNull(await provider.connection.getAccountInfo(deriveListing(mints[0]).listing));
+    assert.isNotNull(await provider.connection.getAccountInfo(deriveListing(auctioned).listing));
+    const expected = (RATE * 3 * step) / LAMPORTS_PER_SOL;
+    assert.equal(Number((await spl.getAccount(provider.connection, takerRewards)).amount) - rewardsBefore, expected);
+
+    await program.methods
+      .setRewardsRate(new anchor.BN(0))
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+  });
+
+  it("only lets the admin set the rewards rate", async () => {
+    try {
+      await program.methods
+        .setRewardsRate(new anchor.BN(1))
+        .accountsPartial({ admin: maker.publicKey, marketplace })
+        .signers([maker])
+        .rpc();
+      assert.fail("setRewardsRate should have failed");
+    } catch (err) {
+      assert.include(err.message, "ConstraintHasOne");
+    }
+  });
+
+  it("caps the rewards rate", async () => {
+    try {
+      await program.methods
+        .setRewardsRate(new anchor.BN(1_000_000_001))
+        .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+        .rpc();
+      assert.fail("setRewardsRate above the cap should have failed");
+    } catch (err) {
+      assert.include(err.message, "InvalidRewardsRate");
+    }
+  });
+
+  it("mints rewards to both sides of a sale at the admin's rate", async () => {
+    // One whole reward token (6 decimals) per SOL of sale price.
+    const RATE = 1_000_000;
+    await program.methods
+      .setRewardsRate(new anchor.BN(RATE))
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+
+    const rewardsOf = async (owner: PublicKey) =>
+      Number((await spl.getAccount(provider.connection, spl.getAssociatedTokenAddressSync(rewardsMint, owner))).amount);
+    const before = await Promise.all([maker.publicKey, taker.publicKey].map(rewardsOf));
+
+    const mint = await mintNft();
+    await list(mint);
+    await purchase(mint);
+    await placeBid(3);
+    await acceptBid(3, await mintNft());
+
+    // One SOL purchase and one SOL bid.
+    const expected = (2 * RATE * PRICE.toNumber()) / LAMPORTS_PER_SOL;
+    assert.equal((await rewardsOf(maker.publicKey)) - before[0], expected);
+    assert.equal((await rewardsOf(taker.publicKey)) - before[1], expected);
+
+    await program.methods
+      .setRewardsRate(new anchor.BN(0))
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+  });
+
+  it("mints no rewards on token sales or on buying your own listing", async () => {
+    await program.methods
+      .setRewardsRate(new anchor.BN(1_000_000))
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+
+    const rewardsOf = async (owner: PublicKey) =>
+      Number((await spl.getAccount(provider.connection, spl.getAssociatedTokenAddressSync(rewardsMint, owner))).amount);
+    const before = await Promise.all([maker.publicKey, taker.publicKey].map(rewardsOf));
+
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    const usdc = await spl.createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const takerUsdc = await spl.createAssociatedTokenAccount(provider.connection, payer, usdc, taker.publicKey);
+    await spl.mintTo(provider.connection, payer, usdc, takerUsdc, payer, PRICE.toNumber());
+    await program.methods
+      .allowPaymentMint()
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, paymentMint: usdc, acceptedMint: deriveAcceptedMint(usdc) })
+      .rpc();
+    const sold = await mintNft();
+    await list(sold, usdc);
+    await purchase(sold, [maker.publicKey], usdc);
+
+    const own = await mintNft();
+    const { listing, vault } = await list(own);
+    await program.methods
+      .purchase()
+      .accountsPartial({
+        taker: maker.publicKey,
+        maker: maker.publicKey,
+        marketplace,
+        makerMint: own,
+        listing,
+        vault,
+        treasury,
+        rewardsMint,
+        tokenProgram: spl.TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([{ pubkey: maker.publicKey, isWritable: true, isSigner: false }])
+      .signers([maker])
+      .rpc();
+
+    assert.equal(await rewardsOf(maker.publicKey), before[0]);
+    assert.equal(await rewardsOf(taker.publicKey), before[1]);
+
+    await program.methods
+      .disallowPaymentMint()
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, acceptedMint: deriveAcceptedMint(usdc) })
+      .rpc();
+    await program.methods
+      .setRewardsRate(new anchor.BN(0))
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+  });
+
+  it("caps the fee and restricts updates to the admin", async () => {
+    try {
+      await program.methods
+        .updateFee(1_001)
+        .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+        .rpc();
+      assert.fail("updateFee above the cap should have failed");
+    } catch (err) {
+      assert.include(err.message, "InvalidFee");
+    }
+
+    await program.methods
+      .updateFee(500)
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+    assert.equal((await program.account.marketplace.fetch(marketplace)).fee, 500);
+
+    await program.methods
+      .updateFee(FEE_BPS)
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+  });
+
+  it("withdraws collected fees but keeps the treasury rent-exempt", async () => {
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(0);
+    const available = (await provider.connection.getBalance(treasury)) - rent;
+    assert.isAbove(available, 0);
+
+    try {
+      await program.methods
+        .withdrawTreasury(new anchor.BN(available + 1))
+        .accountsPartial({ admin: provider.wallet.publicKey, marketplace, treasury })
+        .rpc();
+      assert.fail("withdrawTreasury should have failed");
+    } catch (err) {
+      assert.include(err.message, "InsufficientTreasury");
+    }
+
+    await program.methods
+      .withdrawTreasury(new anchor.BN(available))
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace, treasury })
+      .rpc();
+    assert.equal(await provider.connection.getBalance(treasury), rent);
+  });
+
+  it("hands admin rights over once the new admin accepts", async () => {
+    await program.methods
+      .transferAdmin(maker.publicKey)
+      .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+      .rpc();
+    assert.isTrue((await program.account.marketplace.fetch(marketplace)).admin.equals(provider.wallet.publicKey));
+
+    try {
+      await program.methods
+        .acceptAdmin()
+        .accountsPartial({ newAdmin: taker.publicKey, marketplace })
+        .signers([taker])
+        .rpc();
+      assert.fail("acceptAdmin by anyone but the proposed admin should have failed");
+    } catch (err) {
+      assert.include(err.message, "NotPendingAdmin");
+    }
+
+    await program.methods
+      .acceptAdmin()
+      .accountsPartial({ newAdmin: maker.publicKey, marketplace })
+      .signers([maker])
+      .rpc();
+    const account = await program.account.marketplace.fetch(marketplace);
+    assert.isTrue(account.admin.equals(maker.publicKey));
+    assert.isNull(account.pendingAdmin);
+
+    try {
+      await program.methods
+        .updateFee(0)
+        .accountsPartial({ admin: provider.wallet.publicKey, marketplace })
+        .rpc();
+      assert.fail("the previous admin should have been rejected");
+    } catch (err) {
+      assert.include(err.message, "ConstraintHasOne");
+    }
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+    await program.methods
+      .transferAdmin(provider.wallet.publicKey)
+      .accountsPartial({ admin: maker.publicKey, marketplace })
+      .signers([maker])
+      .rpc();
+    await program.methods
+      .acceptAdmin()
+      .accountsPartial({ newAdmin: provider.wallet.publicKey, marketplace })
+      .rpc();
   });
 });